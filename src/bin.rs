@@ -1,18 +1,108 @@
 use std::env;
-use std::fs;
+use std::fmt::Display;
+use std::fs::{self, File};
+use std::io::BufReader;
 
 use aoc_restore_snow_operations::{
     day_1, day_10, day_11, day_12, day_13, day_14, day_15, day_16, day_17, day_18, day_19, day_2,
-    day_20, day_21, day_3, day_4, day_5, day_6, day_7, day_8, day_9,
+    day_20, day_21, day_3, day_4, day_5, day_6, day_7, day_8, day_9, input::Input, streaming,
 };
 
+// Feeds the input to the solver without reading it all into memory, and prints the final total
+fn print_streamed<S: streaming::LineSolver>(input: &str, solver: S)
+where
+    S::Total: Display,
+{
+    let file = File::open(input).expect("Should have been able to open the file");
+    match streaming::final_total(BufReader::new(file), solver) {
+        Ok(Some(total)) => println!("{}", total),
+        Ok(None) => println!("Empty input"),
+        Err(error) => eprintln!("{}", error),
+    }
+}
+
+// Prints the answer, or the error and exits if there is none
+fn print_answer<T: Display, E: Display>(answer: Result<T, E>) {
+    match answer {
+        Ok(answer) => println!("{}", answer),
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    }
+}
+
+// Days whose input can be read a line at a time, returning false for any other day
+fn run_streamed(day: usize, input: &str) -> bool {
+    match day {
+        1 => {
+            print_streamed(
+                input,
+                day_1::CalibrationSum::new(day_1::Vocabulary::digits()),
+            );
+            print_streamed(
+                input,
+                day_1::CalibrationSum::new(day_1::Vocabulary::english()),
+            );
+        }
+        2 => {
+            let bag = day_2::CubeSet::new(&[("red", 12), ("green", 13), ("blue", 14)]);
+            print_streamed(input, day_2::PossibleGameIds::new(bag));
+            print_streamed(
+                input,
+                day_2::MinimumPowerSum::new(&["red", "green", "blue"]),
+            );
+        }
+        4 => {
+            print_streamed(input, day_4::ScratchcardPoints::default());
+            print_streamed(
+                input,
+                day_4::ScratchcardCascade::new(day_4::PayoutRule::standard()),
+            );
+        }
+        7 => {
+            for ruleset in [day_7::Ruleset::standard(), day_7::Ruleset::jokers()] {
+                print_streamed(input, day_7::TotalWinnings::new(ruleset).unwrap());
+            }
+        }
+        9 => {
+            print_streamed(input, day_9::ExtrapolatedSum::after(1));
+            print_streamed(input, day_9::ExtrapolatedSum::before(1));
+        }
+        12 => {
+            print_streamed(input, day_12::ArrangementSum::new(1));
+            print_streamed(input, day_12::ArrangementSum::new(5));
+        }
+        15 => {
+            print_streamed(input, day_15::VerificationNumber::default());
+            print_streamed(input, day_15::FocusingPower::default());
+        }
+        _ => return false,
+    }
+    true
+}
+
 pub fn main() {
     let args: Vec<String> = env::args().collect();
     let day = &args[1].parse::<usize>().unwrap();
     let input = &args[2];
     println!("Running day {} reading input from {}", day, input);
 
+    if args.get(3).map(|arg| arg.as_str()) == Some("--stream") {
+        if !run_streamed(*day, input) {
+            eprintln!("Day {} cannot be streamed", day);
+        }
+        return;
+    }
+
     let contents = fs::read_to_string(input).expect("Should have been able to read the file");
+    let normalised = Input::new(&contents);
+    if normalised.has_tabs() {
+        eprintln!("Warning: input contains tabs");
+    }
+    if normalised.has_non_ascii() {
+        eprintln!("Warning: input contains non-ASCII characters");
+    }
 
     match day {
         1 => {
@@ -42,11 +132,11 @@ pub fn main() {
             println!("{}", day_5::day_5_get_lowest_location_part_2(&contents));
         }
         6 => {
-            println!(
-                "{}",
-                day_6::day_6_product_of_ways_to_beat_each_race(&contents)
+            print_answer(
+                day_6::day_6_product_of_race_wins(&contents)
+                    .ok_or("Product of the ways to win does not fit in 128 bits"),
             );
-            println!("{}", day_6::day_6_get_number_of_ways_to_win(&contents));
+            println!("{}", day_6::day_6_solve_race_part_2(&contents).count);
         }
         7 => {
             println!("{}", day_7::day_7_calculate_total_winnings(&contents));
@@ -87,10 +177,13 @@ pub fn main() {
             );
         }
         12 => {
-            println!("{}", day_12::day_12_sum_of_possible_arrangements(&contents));
             println!(
                 "{}",
-                day_12::day_12_sum_of_possible_arrangements_part_2(&contents)
+                day_12::day_12_sum_of_arrangements(&contents, 1).unwrap()
+            );
+            println!(
+                "{}",
+                day_12::day_12_sum_of_arrangements(&contents, 5).unwrap()
             );
         }
         13 => {
@@ -115,8 +208,8 @@ pub fn main() {
             );
         }
         16 => {
-            println!("{}", day_16::day_16_count_energized_tiles(&contents));
-            println!("{}", day_16::day_16_count_most_energized_tiles(&contents));
+            print_answer(day_16::day_16_count_energized_tiles(&contents));
+            print_answer(day_16::day_16_count_most_energized_tiles(&contents));
         }
         17 => {
             println!("{}", day_17::day_17_find_lowest_heat_loss(&contents));
@@ -1,6 +1,11 @@
 use wasm_bindgen::prelude::*;
 
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+use crate::input::Input;
+use crate::streaming::LineSolver;
 
 /// Returns a 2 digit integer given a string containing at least one digit
 ///
@@ -32,69 +37,264 @@ fn get_calibration_value(line: &str) -> i32 {
 #[wasm_bindgen]
 pub fn day_1_get_sum_of_calibration_values_in_document(document: &str) -> i32 {
     let mut ans = 0;
-    for line in document.split("\n") {
+    for line in Input::new(document).lines() {
         ans = ans + get_calibration_value(line)
     }
     ans
 }
 
-/// Returns a 2 digit integer given a string containing at least one digit or digit spelt out with letters
+/// Tokens which stand for digits, such as `"7"` or `"seven"`
+#[derive(Debug, Clone)]
+pub struct Vocabulary {
+    /// Calibration values are the first digit times the base plus the last digit
+    pub base: usize,
+    pub tokens: Vec<(String, usize)>,
+}
+
+impl Vocabulary {
+    /// Empty tokens are ignored
+    pub fn new(base: usize, tokens: &[(&str, usize)]) -> Vocabulary {
+        Vocabulary {
+            base,
+            tokens: tokens
+                .iter()
+                .filter(|(token, _)| !token.is_empty())
+                .map(|(token, value)| (token.to_string(), *value))
+                .collect(),
+        }
+    }
+
+    /// The digits 0 to 9
+    pub fn digits() -> Vocabulary {
+        Vocabulary {
+            base: 10,
+            tokens: (0..10).map(|digit| (digit.to_string(), digit)).collect(),
+        }
+    }
+
+    /// The digits 0 to 9 and the words one to nine, as in part 2
+    pub fn english() -> Vocabulary {
+        Vocabulary::digits().with(&[
+            ("one", 1),
+            ("two", 2),
+            ("three", 3),
+            ("four", 4),
+            ("five", 5),
+            ("six", 6),
+            ("seven", 7),
+            ("eight", 8),
+            ("nine", 9),
+        ])
+    }
+
+    /// Adds more tokens, e.g. `Vocabulary::english().with(&[("zero", 0)])`
+    pub fn with(mut self, tokens: &[(&str, usize)]) -> Vocabulary {
+        self.tokens
+            .extend(Vocabulary::new(self.base, tokens).tokens);
+        self
+    }
+}
+
+/// A token found in a line, with the byte offsets it spans
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenMatch {
+    pub token: String,
+    pub value: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Aho-Corasick automaton finding every token of a vocabulary in a single pass, including overlapping ones
+#[derive(Debug)]
+pub struct Matcher {
+    vocabulary: Vocabulary,
+    goto: Vec<HashMap<char, usize>>,
+    fail: Vec<usize>,
+    /// Tokens ending at each state, including those of its fail states
+    output: Vec<Vec<usize>>,
+}
+
+impl Matcher {
+    pub fn new(vocabulary: Vocabulary) -> Matcher {
+        let mut goto: Vec<HashMap<char, usize>> = vec![HashMap::new()];
+        let mut output: Vec<Vec<usize>> = vec![Vec::new()];
+
+        // Trie of the tokens
+        for (k, (token, _)) in vocabulary.tokens.iter().enumerate() {
+            let mut state = 0;
+            for c in token.chars() {
+                state = match goto[state].get(&c) {
+                    Some(next) => *next,
+                    None => {
+                        goto.push(HashMap::new());
+                        output.push(Vec::new());
+                        let next = goto.len() - 1;
+                        goto[state].insert(c, next);
+                        next
+                    }
+                };
+            }
+            output[state].push(k);
+        }
+
+        // Fail links point to the state for the longest proper suffix which is also in the trie, found breadth first so
+        // that shorter suffixes are always done first
+        let mut fail = vec![0; goto.len()];
+        let mut queue = goto[0].values().copied().collect::<VecDeque<_>>();
+        while let Some(state) = queue.pop_front() {
+            for (c, next) in goto[state].clone() {
+                let mut f = fail[state];
+                while f != 0 && !goto[f].contains_key(&c) {
+                    f = fail[f];
+                }
+                fail[next] = goto[f].get(&c).copied().unwrap_or(0);
+
+                let inherited = output[fail[next]].clone();
+                output[next].extend(inherited);
+                queue.push_back(next);
+            }
+        }
+
+        Matcher {
+            vocabulary,
+            goto,
+            fail,
+            output,
+        }
+    }
+
+    pub fn vocabulary(&self) -> &Vocabulary {
+        &self.vocabulary
+    }
+
+    /// Every token in the line, ordered by where they end
+    pub fn find_overlapping(&self, line: &str) -> Vec<TokenMatch> {
+        let mut matches = Vec::new();
+
+        let mut state = 0;
+        for (i, c) in line.char_indices() {
+            while state != 0 && !self.goto[state].contains_key(&c) {
+                state = self.fail[state];
+            }
+            state = self.goto[state].get(&c).copied().unwrap_or(0);
+
+            let end = i + c.len_utf8();
+            for k in &self.output[state] {
+                let (token, value) = &self.vocabulary.tokens[*k];
+                matches.push(TokenMatch {
+                    token: token.clone(),
+                    value: *value,
+                    start: end - token.len(),
+                    end,
+                });
+            }
+        }
+
+        matches
+    }
+
+    /// The tokens starting first and last in the line, the longer one if several start at the same place
+    pub fn first_and_last(&self, line: &str) -> Option<(TokenMatch, TokenMatch)> {
+        let matches = self.find_overlapping(line);
+
+        let first = matches.iter().min_by_key(|m| (m.start, Reverse(m.end)))?;
+        let last = matches.iter().max_by_key(|m| (m.start, m.end))?;
+
+        Some((first.clone(), last.clone()))
+    }
+}
+
+/// The tokens chosen for a line's calibration value
+#[derive(Debug, PartialEq)]
+pub struct Calibration {
+    pub first: TokenMatch,
+    pub last: TokenMatch,
+    pub value: usize,
+}
+
+/// Returns the calibration value of a line, and which tokens made it, if there are any tokens in it
 ///
 /// # Arguments
 ///
 /// * `line` a line from the newly-improved calibration document
-fn get_calibration_value_part_2(line: &str) -> usize {
-    // Parse digits first, taking note of when they occur
-    let (mut first_digit_position, mut first_digit): (Option<usize>, Option<char>) = (None, None);
-    let (mut last_digit_position, mut last_digit): (Option<usize>, Option<char>) = (None, None);
-    for (i, c) in line.chars().enumerate() {
-        if c.is_digit(10) {
-            if first_digit.is_none() {
-                first_digit_position = Some(i);
-                first_digit = Some(c);
-            }
-            last_digit_position = Some(i);
-            last_digit = Some(c);
-        }
+/// * `matcher` the tokens which count as digits
+pub fn explain_calibration_value(line: &str, matcher: &Matcher) -> Option<Calibration> {
+    let (first, last) = matcher.first_and_last(line)?;
+    let value = first.value * matcher.vocabulary().base + last.value;
+
+    Some(Calibration { first, last, value })
+}
+
+/// Returns the calibration of each line in a document, None for lines without any tokens
+///
+/// # Arguments
+///
+/// * `document` a newly-improved calibration document
+/// * `vocabulary` the tokens which count as digits
+pub fn day_1_explain_calibration_values(
+    document: &str,
+    vocabulary: &Vocabulary,
+) -> Vec<Option<Calibration>> {
+    let matcher = Matcher::new(vocabulary.clone());
+    Input::new(document)
+        .lines()
+        .map(|line| explain_calibration_value(line, &matcher))
+        .collect()
+}
+
+#[derive(Debug, PartialEq)]
+pub struct NoDigitsError {
+    pub line: String,
+}
+
+impl fmt::Display for NoDigitsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "No digits in {}", self.line)
     }
+}
 
-    let letters_digits = HashMap::from([
-        ("one", '1'),
-        ("two", '2'),
-        ("three", '3'),
-        ("four", '4'),
-        ("five", '5'),
-        ("six", '6'),
-        ("seven", '7'),
-        ("eight", '8'),
-        ("nine", '9'),
-    ]);
-
-    // Look for word in line
-    // If not present, continue to next word
-    // If present, check if the first digit has been found yet or occurs after it
-    // If so, it is the new first digit
-    // Look for word from the right, same logic
-    for (letters, digit) in &letters_digits {
-        let position = line.find(letters);
-        if position.is_none() {
-            continue;
-        }
-        if first_digit_position.is_none() || (position.unwrap() < first_digit_position.unwrap()) {
-            first_digit_position = position;
-            first_digit = Some(*digit);
-        }
+/// Running sum of the calibration values of a document read a line at a time
+#[derive(Debug)]
+pub struct CalibrationSum {
+    matcher: Matcher,
+    total: usize,
+}
 
-        let r_position = line.rfind(letters);
-        if last_digit_position.is_none() || r_position > last_digit_position {
-            last_digit_position = r_position;
-            last_digit = Some(*digit);
+impl CalibrationSum {
+    pub fn new(vocabulary: Vocabulary) -> CalibrationSum {
+        CalibrationSum {
+            matcher: Matcher::new(vocabulary),
+            total: 0,
         }
     }
+}
 
-    format!("{}{}", first_digit.unwrap(), last_digit.unwrap())
-        .parse::<usize>()
-        .unwrap()
+impl LineSolver for CalibrationSum {
+    type Total = usize;
+    type Error = NoDigitsError;
+
+    fn feed(&mut self, line: &str) -> Result<(), NoDigitsError> {
+        let calibration =
+            explain_calibration_value(line, &self.matcher).ok_or_else(|| NoDigitsError {
+                line: line.to_string(),
+            })?;
+        self.total += calibration.value;
+        Ok(())
+    }
+
+    fn total(&self) -> usize {
+        self.total
+    }
+}
+
+/// Returns a 2 digit integer given a string containing at least one digit or digit spelt out with letters
+///
+/// # Arguments
+///
+/// * `line` a line from the newly-improved calibration document
+/// * `matcher` a matcher for the English vocabulary
+fn get_calibration_value_part_2(line: &str, matcher: &Matcher) -> usize {
+    explain_calibration_value(line, matcher).unwrap().value
 }
 
 /// Returns a the sum of calibration values in a valid newly-improved calibration document based on part 2
@@ -104,9 +304,11 @@ fn get_calibration_value_part_2(line: &str) -> usize {
 /// * `document` a valid newly-improved calibration document
 #[wasm_bindgen]
 pub fn day_1_get_sum_of_calibration_values_in_document_part_2(document: &str) -> usize {
+    let matcher = Matcher::new(Vocabulary::english());
+
     let mut ans = 0;
-    for line in document.split("\n") {
-        ans = ans + get_calibration_value_part_2(line)
+    for line in Input::new(document).lines() {
+        ans += get_calibration_value_part_2(line, &matcher)
     }
     ans
 }
@@ -115,6 +317,8 @@ pub fn day_1_get_sum_of_calibration_values_in_document_part_2(document: &str) ->
 mod tests {
     use super::*;
 
+    use crate::streaming::{final_total, running_totals};
+
     macro_rules! generate_parse_calibration_value_tests {
         ($($name:ident: $value:expr,)*) => {
         $(
@@ -153,7 +357,8 @@ mod tests {
             #[test]
             fn $name() {
                 let (input, expected) = $value;
-                assert_eq!(expected, get_calibration_value_part_2(input));
+                let matcher = Matcher::new(Vocabulary::english());
+                assert_eq!(expected, get_calibration_value_part_2(input, &matcher));
             }
         )*
         }
@@ -178,6 +383,94 @@ mod tests {
         test_get_calibration_value_part_2_one3one: ("one3one", 11),
     }
 
+    #[test]
+    fn test_find_overlapping() {
+        let matcher = Matcher::new(Vocabulary::english());
+        assert_eq!(
+            vec![("eight", 0, 5), ("two", 4, 7), ("three", 7, 12)],
+            matcher
+                .find_overlapping("eightwothree")
+                .iter()
+                .map(|m| (m.token.as_str(), m.start, m.end))
+                .collect::<Vec<_>>()
+        );
+
+        // Tokens inside other tokens
+        let matcher = Matcher::new(Vocabulary::new(10, &[("he", 1), ("she", 2), ("hers", 3)]));
+        assert_eq!(
+            vec![("she", 1, 4), ("he", 2, 4), ("hers", 2, 6)],
+            matcher
+                .find_overlapping("ushers")
+                .iter()
+                .map(|m| (m.token.as_str(), m.start, m.end))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(None, matcher.first_and_last("nothing"));
+    }
+
+    #[test]
+    fn test_day_1_explain_calibration_values() {
+        let calibrations = day_1_explain_calibration_values(
+            "xtwone3four\nnothing\nzero9",
+            &Vocabulary::english().with(&[("zero", 0)]),
+        );
+        let calibration = calibrations[0].as_ref().unwrap();
+        assert_eq!(
+            ("two", "four"),
+            (
+                calibration.first.token.as_str(),
+                calibration.last.token.as_str()
+            )
+        );
+        assert_eq!((1, 7), (calibration.first.start, calibration.last.start));
+        assert_eq!(24, calibration.value);
+        assert_eq!(None, calibrations[1]);
+        assert_eq!(9, calibrations[2].as_ref().unwrap().value);
+
+        // Other languages, including non-ASCII words, and other bases
+        let german = Vocabulary::new(10, &[("eins", 1), ("zwei", 2), ("drei", 3), ("fünf", 5)]);
+        assert_eq!(
+            Some(51),
+            day_1_explain_calibration_values("xfünfzweieins", &german)[0]
+                .as_ref()
+                .map(|calibration| calibration.value)
+        );
+        let hex = Vocabulary::new(16, &[("a", 10), ("f", 15), ("1", 1), ("beef", 0xbeef)]);
+        assert_eq!(
+            Some(0xbeef * 16 + 15),
+            day_1_explain_calibration_values("xbeef", &hex)[0]
+                .as_ref()
+                .map(|calibration| calibration.value)
+        );
+    }
+
+    #[test]
+    fn test_calibration_sum() {
+        let document = "two1nine\neightwothree\nabcone2threexyz\n";
+        assert_eq!(
+            vec![29, 112, 125],
+            running_totals(
+                document.as_bytes(),
+                CalibrationSum::new(Vocabulary::english())
+            )
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+        );
+        assert_eq!(
+            Some(50),
+            final_total(
+                "1abc2\r\npqr3stu8vwx\r\n".as_bytes(),
+                CalibrationSum::new(Vocabulary::digits())
+            )
+            .unwrap()
+        );
+        assert!(final_total(
+            "1\nabc".as_bytes(),
+            CalibrationSum::new(Vocabulary::digits())
+        )
+        .is_err());
+    }
+
     #[test]
     fn test_get_sum_of_calibration_values_in_document_part_2() {
         assert_eq!(
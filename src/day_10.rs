@@ -3,15 +3,20 @@ use wasm_bindgen::prelude::*;
 use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
 
-fn parse_sketch(
-    sketch: &str,
-) -> (
-    (isize, isize),
-    HashMap<(isize, isize), [(isize, isize); 2]>,
-    Vec<Vec<char>>,
-) {
+use crate::input::Input;
+
+pub struct Sketch {
+    // Every row of tiles, with S replaced by the pipe it stands for
+    pub tiles: Vec<Vec<char>>,
+    pub start: (isize, isize),
+    // Every tile of the main loop in order, starting from S
+    pub main_loop: Vec<(isize, isize)>,
+}
+
+pub fn parse_sketch(sketch: &str) -> Sketch {
     let mut indexable_sketch: Vec<Vec<char>> = Vec::new();
-    for line in sketch.split("\n").map(|line| line.trim()) {
+    let sketch = Input::new(sketch);
+    for line in sketch.lines() {
         let mut row: Vec<char> = Vec::new();
         for c in line.chars() {
             row.push(c);
@@ -95,7 +100,21 @@ fn parse_sketch(
         j = 0;
     }
 
-    (start, pipes, indexable_sketch)
+    let mut main_loop = vec![start];
+    let mut prev: (isize, isize) = start;
+    let mut curr: (isize, isize) = pipes.get(&start).unwrap()[0];
+    while curr != start {
+        main_loop.push(curr);
+        let next = next_pipe(curr, prev, &pipes);
+        prev = curr;
+        curr = next;
+    }
+
+    Sketch {
+        tiles: indexable_sketch,
+        start,
+        main_loop,
+    }
 }
 
 fn next_pipe(
@@ -111,113 +130,145 @@ fn next_pipe(
     unreachable!();
 }
 
-#[wasm_bindgen]
-pub fn day_10_find_furthest_point(sketch: &str) -> usize {
-    let (start, pipes, _) = parse_sketch(sketch);
+impl Sketch {
+    // The corners of the main loop in order, starting from S if it is one
+    pub fn vertices(&self) -> Vec<(isize, isize)> {
+        self.main_loop
+            .iter()
+            .filter(|(i, j)| !matches!(self.tiles[*i as usize][*j as usize], '|' | '-'))
+            .copied()
+            .collect()
+    }
 
-    let mut loop_length = 1;
-    let mut prev: (isize, isize) = start;
-    let mut curr: (isize, isize) = pipes.get(&start).unwrap()[0];
+    // Whether each tile is enclosed by the main loop, found by scanning each row and counting how many times the loop
+    // is crossed
+    pub fn inside(&self) -> Vec<Vec<bool>> {
+        let loop_pipes: HashSet<(isize, isize)> = self.main_loop.iter().copied().collect();
 
-    loop {
-        let next = next_pipe(curr, prev, &pipes);
+        let mut inside_tiles = Vec::new();
+        for (i, row) in self.tiles.iter().enumerate() {
+            let mut inside_row = Vec::new();
+            let mut prev_pipe: Option<char> = None;
+            let mut inside = false;
+            for (j, pipe) in row.iter().enumerate() {
+                if !loop_pipes.contains(&(i as isize, j as isize)) {
+                    inside_row.push(inside);
+                    continue;
+                }
+                inside_row.push(false);
 
-        if next == start {
-            if loop_length % 2 == 0 {
-                return loop_length / 2;
-            } else {
-                return (loop_length + 1) / 2;
+                match pipe {
+                    '-' => {}
+                    '|' => inside = !inside,
+                    _ => {
+                        if let Some(p) = prev_pipe {
+                            match pipe {
+                                '7' => {
+                                    if p == 'L' {
+                                        inside = !inside;
+                                    }
+                                }
+                                'J' => {
+                                    if p == 'F' {
+                                        inside = !inside;
+                                    }
+                                }
+                                _ => unreachable!(),
+                            }
+                            prev_pipe = None;
+                        } else {
+                            prev_pipe = Some(*pipe);
+                        }
+                    }
+                }
             }
+            inside_tiles.push(inside_row);
         }
 
-        loop_length = loop_length + 1;
-        prev = curr;
-        curr = next;
+        inside_tiles
     }
-}
-
-#[wasm_bindgen]
-pub fn day_10_find_number_of_tiles_enclosed(sketch: &str) -> usize {
-    let (start, pipes, indexable_sketch) = parse_sketch(sketch);
 
-    let mut row_range: [isize; 2] = [isize::MAX, 0];
-    let mut col_range: [isize; 2] = [isize::MAX, 0];
-    let mut loop_pipes: HashSet<(isize, isize)> = HashSet::new();
-    loop_pipes.insert(start);
+    // The area of the polygon through the centres of the loop's tiles comes from the shoelace formula. Pick's theorem,
+    // A = I + B / 2 - 1, then gives the number of tiles inside it, I, since every tile of the loop is one of the B
+    // points on its boundary.
+    pub fn enclosed_by_area(&self) -> usize {
+        let vertices = self.vertices();
 
-    let mut prev: (isize, isize) = start;
-    let mut curr: (isize, isize) = pipes.get(&start).unwrap()[0];
-
-    loop {
-        loop_pipes.insert(curr);
-
-        if curr.0 < row_range[0] {
-            row_range[0] = curr.0
-        }
-        if curr.0 > row_range[1] {
-            row_range[1] = curr.0
-        }
-        if curr.1 < col_range[0] {
-            col_range[0] = curr.1
+        let mut twice_area: isize = 0;
+        for (k, (i, j)) in vertices.iter().enumerate() {
+            let (next_i, next_j) = vertices[(k + 1) % vertices.len()];
+            twice_area += i * next_j - next_i * j;
         }
-        if curr.1 > col_range[1] {
-            col_range[1] = curr.1
-        }
-
-        let next = next_pipe(curr, prev, &pipes);
 
-        if next == start {
-            break;
-        }
-
-        prev = curr;
-        curr = next;
+        // 2A - B is -2 for a loop with nothing inside, so this is done in isize
+        ((twice_area.abs() - self.main_loop.len() as isize) / 2 + 1) as usize
     }
 
-    let mut ans = 0;
+    // The sketch redrawn with box-drawing characters for the main loop, heavy ones for S, and every other tile marked
+    // I if it is enclosed by the loop or O if not
+    pub fn render(&self) -> String {
+        let inside = self.inside();
+        let loop_pipes: HashSet<(isize, isize)> = self.main_loop.iter().copied().collect();
 
-    for i in row_range[0]..row_range[1] + 1 {
-        let mut prev_pipe: Option<char> = None;
-        let mut inside = false;
-        for j in col_range[0]..col_range[1] + 1 {
-            if loop_pipes.contains(&(i, j)) {
-                let pipe = indexable_sketch[i as usize][j as usize];
-                if pipe == '-' {
-                    continue;
-                }
-
-                if pipe == '|' {
-                    inside = !inside;
-                    continue;
-                }
-                if let Some(p) = prev_pipe {
-                    match pipe {
-                        '7' => {
-                            if p == 'L' {
-                                inside = !inside;
-                            }
-                        }
-                        'J' => {
-                            if p == 'F' {
-                                inside = !inside;
-                            }
-                        }
+        let mut rows = Vec::new();
+        for (i, row) in self.tiles.iter().enumerate() {
+            let mut rendered_row = String::new();
+            for (j, pipe) in row.iter().enumerate() {
+                let tile = (i as isize, j as isize);
+                let rendered = match (loop_pipes.contains(&tile), tile == self.start) {
+                    (true, true) => match pipe {
+                        '|' => '┃',
+                        '-' => '━',
+                        'L' => '┗',
+                        'J' => '┛',
+                        '7' => '┓',
+                        'F' => '┏',
                         _ => unreachable!(),
-                    }
-                    prev_pipe = None;
-                } else {
-                    prev_pipe = Some(pipe);
-                }
-                continue;
-            }
-
-            if inside {
-                ans = ans + 1;
+                    },
+                    (true, false) => match pipe {
+                        '|' => '│',
+                        '-' => '─',
+                        'L' => '└',
+                        'J' => '┘',
+                        '7' => '┐',
+                        'F' => '┌',
+                        _ => unreachable!(),
+                    },
+                    _ if inside[i][j] => 'I',
+                    _ => 'O',
+                };
+                rendered_row.push(rendered);
             }
+            rows.push(rendered_row);
         }
+
+        rows.join("\n")
     }
+}
+
+#[wasm_bindgen]
+pub fn day_10_find_furthest_point(sketch: &str) -> usize {
+    parse_sketch(sketch).main_loop.len() / 2
+}
+
+#[wasm_bindgen]
+pub fn day_10_find_number_of_tiles_enclosed(sketch: &str) -> usize {
+    parse_sketch(sketch)
+        .inside()
+        .iter()
+        .flatten()
+        .filter(|inside| **inside)
+        .count()
+}
+
+#[wasm_bindgen]
+pub fn day_10_find_number_of_tiles_enclosed_by_area(sketch: &str) -> usize {
+    parse_sketch(sketch).enclosed_by_area()
+}
 
-    ans
+#[wasm_bindgen]
+pub fn day_10_render_sketch(sketch: &str) -> String {
+    parse_sketch(sketch).render()
 }
 
 #[cfg(test)]
@@ -249,10 +300,42 @@ mod tests {
     }
 
     #[test]
-    fn test_day_10_find_number_of_tiles_enclosed() {
+    fn test_parse_sketch() {
+        let sketch = parse_sketch(
+            r#"-L|F7
+            7S-7|
+            L|7||
+            -L-J|
+            L|-JF"#,
+        );
+        assert_eq!((1, 1), sketch.start);
+        assert_eq!('F', sketch.tiles[1][1]);
         assert_eq!(
-            4,
-            day_10_find_number_of_tiles_enclosed(
+            vec![
+                (1, 1),
+                (2, 1),
+                (3, 1),
+                (3, 2),
+                (3, 3),
+                (2, 3),
+                (1, 3),
+                (1, 2)
+            ],
+            sketch.main_loop
+        );
+        assert_eq!(vec![(1, 1), (3, 1), (3, 3), (1, 3)], sketch.vertices());
+        assert_eq!(1, sketch.enclosed_by_area());
+        assert_eq!("OOOOO\nO┏─┐O\nO│I│O\nO└─┘O\nOOOOO", sketch.render());
+    }
+
+    #[test]
+    fn test_day_10_find_number_of_tiles_enclosed() {
+        for (expected, sketch) in [
+            // Loops with nothing inside
+            (0, "S7\nLJ"),
+            (0, "S-7\nL-J"),
+            (
+                4,
                 r#"...........
                 .S-------7.
                 .|F-----7|.
@@ -261,12 +344,10 @@ mod tests {
                 .|L-7.F-J|.
                 .|..|.|..|.
                 .L--J.L--J.
-                ..........."#
-            )
-        );
-        assert_eq!(
-            4,
-            day_10_find_number_of_tiles_enclosed(
+                ..........."#,
+            ),
+            (
+                4,
                 r#"...........
                 .S-------7.
                 .|F-----7|.
@@ -275,12 +356,10 @@ mod tests {
                 .|L-7OF-J|.
                 .|II|O|II|.
                 .L--JOL--J.
-                .....O....."#
-            )
-        );
-        assert_eq!(
-            8,
-            day_10_find_number_of_tiles_enclosed(
+                .....O....."#,
+            ),
+            (
+                8,
                 r#"OF----7F7F7F7F-7OOOO
               O|F--7||||||||FJOOOO
               O||OFJ||||||||L7OOOO
@@ -290,12 +369,10 @@ mod tests {
               OOOOL7IF7||L7|IL7L7|
               OOOOO|FJLJ|FJ|F7|OLJ
               OOOOFJL-7O||O||||OOO
-              OOOOL---JOLJOLJLJOOO"#
-            )
-        );
-        assert_eq!(
-            10,
-            day_10_find_number_of_tiles_enclosed(
+              OOOOL---JOLJOLJLJOOO"#,
+            ),
+            (
+                10,
                 r#"FF7FSF7F7F7F7F7F---7
               L|LJ||||||||||||F--J
               FL-7LJLJ||||||LJL-77
@@ -305,8 +382,15 @@ mod tests {
               |FFJF7L7F-JF7IIL---7
               7-L-JL7||F7|L7F-7F7|
               L.L7LFJ|||||FJL7||LJ
-              L7JLJL-JLJLJL--JLJ.L"#
-            )
-        );
+              L7JLJL-JLJLJL--JLJ.L"#,
+            ),
+        ] {
+            assert_eq!(expected, day_10_find_number_of_tiles_enclosed(sketch));
+            // Pick's theorem must agree with scanning the rows
+            assert_eq!(
+                expected,
+                day_10_find_number_of_tiles_enclosed_by_area(sketch)
+            );
+        }
     }
 }
@@ -1,7 +1,10 @@
 use wasm_bindgen::prelude::*;
 
-use std::cmp::{max, min};
 use std::collections::HashSet;
+use std::convert::TryInto;
+use std::fmt;
+
+use crate::input::Input;
 
 fn parse_image(
     image: &str,
@@ -16,7 +19,8 @@ fn parse_image(
 
     let (mut n, mut m) = (0, 0);
 
-    for (i, line) in image.split("\n").map(|line| line.trim()).enumerate() {
+    let image = Input::new(image);
+    for (i, line) in image.lines().enumerate() {
         if i == 0 {
             for (j, _) in line.chars().enumerate() {
                 empty_cols.insert(j);
@@ -37,87 +41,182 @@ fn parse_image(
     ((n, m), empty_rows, empty_cols, galaxies)
 }
 
+// An expansion factor of 0, which would remove empty rows and columns rather than expand them
+#[derive(Debug, PartialEq)]
+pub struct ZeroExpansionError;
+
+impl fmt::Display for ZeroExpansionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The expansion factor must be at least 1")
+    }
+}
+
+// Coordinates of each galaxy, in reading order, after every empty row and column is replaced by `factor` of them.
+// The factor must be at least 1.
 fn expand_universe(
     size: (usize, usize),
     empty_rows: &HashSet<usize>,
     empty_cols: &HashSet<usize>,
     galaxies: &HashSet<(usize, usize)>,
-    times: usize,
-) -> HashSet<(usize, usize)> {
+    factor: u128,
+) -> Vec<(u128, u128)> {
     let (n, m) = size;
 
-    let mut expanded_rows = vec![0; n];
-    let mut skipped_rows = 0;
-    for i in 0..n {
-        if empty_rows.contains(&i) {
-            skipped_rows = skipped_rows + (times - 1);
+    let expand = |length: usize, empty: &HashSet<usize>| {
+        let mut expanded = Vec::with_capacity(length);
+        let mut skipped = 0;
+        for i in 0..length {
+            if empty.contains(&i) {
+                skipped += factor - 1;
+            }
+            expanded.push(i as u128 + skipped);
         }
-        expanded_rows[i] = i + skipped_rows;
-    }
+        expanded
+    };
+    let expanded_rows = expand(n, empty_rows);
+    let expanded_cols = expand(m, empty_cols);
 
-    let mut expanded_cols = vec![0; m];
-    let mut skipped_cols = 0;
-    for i in 0..m {
-        if empty_cols.contains(&i) {
-            skipped_cols = skipped_cols + (times - 1);
-        }
-        expanded_cols[i] = i + skipped_cols;
-    }
+    let mut galaxies = galaxies.iter().copied().collect::<Vec<_>>();
+    galaxies.sort();
 
-    let mut expanded_galaxies = HashSet::<(usize, usize)>::new();
+    galaxies
+        .iter()
+        .map(|(i, j)| (expanded_rows[*i], expanded_cols[*j]))
+        .collect()
+}
+
+// Sum of |a - b| over every pair, from the sorted values and the running sum of the values before each one
+fn sum_pairwise_differences(mut values: Vec<u128>) -> u128 {
+    values.sort_unstable();
 
-    for galaxy in galaxies {
-        let (i, j) = *galaxy;
-        expanded_galaxies.insert((expanded_rows[i], expanded_cols[j]));
+    let mut acc = 0;
+    let mut prefix_sum = 0;
+    for (k, value) in values.iter().enumerate() {
+        acc += value * k as u128 - prefix_sum;
+        prefix_sum += value;
     }
 
-    expanded_galaxies
+    acc
 }
 
-#[wasm_bindgen]
-pub fn day_11_sum_lengths_between_galaxies(image: &str) -> usize {
-    let (size, empty_rows, empty_cols, galaxies) = parse_image(image);
-    let expanded_galaxies = expand_universe(size, &empty_rows, &empty_cols, &galaxies, 2)
-        .iter()
-        .map(|x| *x)
-        .collect::<Vec<(usize, usize)>>();
+fn sum_lengths_between_galaxies(galaxies: &[(u128, u128)]) -> u128 {
+    // Manhattan distances separate into the distances along each axis
+    sum_pairwise_differences(galaxies.iter().map(|(x, _)| *x).collect())
+        + sum_pairwise_differences(galaxies.iter().map(|(_, y)| *y).collect())
+}
 
-    let n = expanded_galaxies.len();
+// For every galaxy, the distance to the closest other galaxy.
+// Each sweep finds the closest galaxy up and to the right, i.e. minimising x + y over galaxies with x' >= x and y' >= y,
+// with the galaxies reflected so that the four sweeps cover every direction.
+fn nearest_galaxy_distances(galaxies: &[(u128, u128)]) -> Vec<Option<u128>> {
+    let n = galaxies.len();
+    let mut nearest: Vec<Option<u128>> = vec![None; n];
 
-    let mut acc = 0;
-    for i in 0..n {
-        let (x0, y0) = expanded_galaxies[i];
-        for j in i + 1..n {
-            let (x1, y1) = expanded_galaxies[j];
+    for (flip_x, flip_y) in [(false, false), (true, false), (false, true), (true, true)] {
+        let points = galaxies
+            .iter()
+            .map(|(x, y)| {
+                (
+                    if flip_x { -(*x as i128) } else { *x as i128 },
+                    if flip_y { -(*y as i128) } else { *y as i128 },
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // Rank of each y coordinate, largest first, so that y' >= y becomes a prefix
+        let mut ys = points.iter().map(|(_, y)| *y).collect::<Vec<_>>();
+        ys.sort_unstable_by(|a, b| b.cmp(a));
+        ys.dedup();
+
+        let mut order = (0..n).collect::<Vec<_>>();
+        order.sort_unstable_by(|a, b| points[*b].cmp(&points[*a]));
+
+        // Fenwick tree of the minimum x + y and which galaxy it belongs to
+        let mut tree: Vec<Option<(i128, usize)>> = vec![None; ys.len() + 1];
+        for k in order {
+            let (x, y) = points[k];
+            let rank = ys.binary_search_by(|probe| y.cmp(probe)).unwrap() + 1;
 
-            acc = acc + (max(x1, x0) - min(x1, x0)) + (max(y1, y0) - min(y1, y0));
+            let mut best = None;
+            let mut r = rank;
+            while r > 0 {
+                best = min_entry(best, tree[r]);
+                r -= r & r.wrapping_neg();
+            }
+            if let Some((sum, _)) = best {
+                let distance = (sum - (x + y)) as u128;
+                nearest[k] = Some(nearest[k].map_or(distance, |d| d.min(distance)));
+            }
+
+            let mut r = rank;
+            while r <= ys.len() {
+                tree[r] = min_entry(tree[r], Some((x + y, k)));
+                r += r & r.wrapping_neg();
+            }
         }
     }
 
-    acc
+    nearest
 }
 
-#[wasm_bindgen]
-pub fn day_11_sum_lengths_between_galaxies_part_2(image: &str) -> usize {
+fn min_entry(a: Option<(i128, usize)>, b: Option<(i128, usize)>) -> Option<(i128, usize)> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(if b.0 < a.0 { b } else { a }),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+pub fn day_11_sum_lengths_between_galaxies_with_expansion(
+    image: &str,
+    factor: u128,
+) -> Result<u128, ZeroExpansionError> {
+    if factor == 0 {
+        return Err(ZeroExpansionError);
+    }
     let (size, empty_rows, empty_cols, galaxies) = parse_image(image);
-    let expanded_galaxies = expand_universe(size, &empty_rows, &empty_cols, &galaxies, 1000000)
-        .iter()
-        .map(|x| *x)
-        .collect::<Vec<(usize, usize)>>();
+    let expanded_galaxies = expand_universe(size, &empty_rows, &empty_cols, &galaxies, factor);
 
-    let n = expanded_galaxies.len();
+    Ok(sum_lengths_between_galaxies(&expanded_galaxies))
+}
 
-    let mut acc = 0;
-    for i in 0..n {
-        let (x0, y0) = expanded_galaxies[i];
-        for j in i + 1..n {
-            let (x1, y1) = expanded_galaxies[j];
+// A galaxy's position in the image and the distance to its nearest neighbour, if it has one
+pub type NearestGalaxy = ((usize, usize), Option<u128>);
 
-            acc = acc + (max(x1, x0) - min(x1, x0)) + (max(y1, y0) - min(y1, y0));
-        }
+// Each galaxy, in reading order, with the distance to its nearest neighbour after expansion
+pub fn day_11_nearest_galaxy_distances(
+    image: &str,
+    factor: u128,
+) -> Result<Vec<NearestGalaxy>, ZeroExpansionError> {
+    if factor == 0 {
+        return Err(ZeroExpansionError);
     }
+    let (size, empty_rows, empty_cols, galaxies) = parse_image(image);
+    let expanded_galaxies = expand_universe(size, &empty_rows, &empty_cols, &galaxies, factor);
 
-    acc
+    let mut galaxies = galaxies.into_iter().collect::<Vec<_>>();
+    galaxies.sort();
+
+    Ok(galaxies
+        .into_iter()
+        .zip(nearest_galaxy_distances(&expanded_galaxies))
+        .collect())
+}
+
+#[wasm_bindgen]
+pub fn day_11_sum_lengths_between_galaxies(image: &str) -> usize {
+    day_11_sum_lengths_between_galaxies_with_expansion(image, 2)
+        .unwrap()
+        .try_into()
+        .unwrap()
+}
+
+#[wasm_bindgen]
+pub fn day_11_sum_lengths_between_galaxies_part_2(image: &str) -> usize {
+    day_11_sum_lengths_between_galaxies_with_expansion(image, 1000000)
+        .unwrap()
+        .try_into()
+        .unwrap()
 }
 
 #[cfg(test)]
@@ -143,7 +242,10 @@ mod tests {
         assert_eq!(HashSet::from([3, 7]), empty_rows);
         assert_eq!(HashSet::from([2, 5, 8]), empty_cols);
 
-        let expanded_galaxies = expand_universe(size, &empty_rows, &empty_cols, &galaxies, 2);
+        let expanded_galaxies = expand_universe(size, &empty_rows, &empty_cols, &galaxies, 2)
+            .iter()
+            .map(|(i, j)| (*i as usize, *j as usize))
+            .collect::<HashSet<_>>();
         let (_, _, _, parsed_expanded_galaxies) = parse_image(
             r#"....#........
         .........#...
@@ -165,4 +267,67 @@ mod tests {
     fn test_day_11_sum_lengths_between_galaxies() {
         assert_eq!(374, day_11_sum_lengths_between_galaxies(EXAMPLE))
     }
+
+    #[test]
+    fn test_day_11_sum_lengths_between_galaxies_with_expansion() {
+        assert_eq!(
+            Ok(374),
+            day_11_sum_lengths_between_galaxies_with_expansion(EXAMPLE, 2)
+        );
+        assert_eq!(
+            Ok(1030),
+            day_11_sum_lengths_between_galaxies_with_expansion(EXAMPLE, 10)
+        );
+        assert_eq!(
+            Ok(8410),
+            day_11_sum_lengths_between_galaxies_with_expansion(EXAMPLE, 100)
+        );
+        // Expansions too large for a usize
+        assert_eq!(
+            Ok(292 + 82 * u64::MAX as u128),
+            day_11_sum_lengths_between_galaxies_with_expansion(EXAMPLE, u64::MAX as u128 + 1)
+        );
+        // Empty rows and columns can't be removed
+        assert_eq!(
+            Err(ZeroExpansionError),
+            day_11_sum_lengths_between_galaxies_with_expansion(EXAMPLE, 0)
+        );
+    }
+
+    #[test]
+    fn test_day_11_nearest_galaxy_distances() {
+        let (size, empty_rows, empty_cols, galaxies) = parse_image(EXAMPLE);
+        for factor in [1, 2, 10, 1000000] {
+            let expanded_galaxies =
+                expand_universe(size, &empty_rows, &empty_cols, &galaxies, factor);
+
+            // Compare against checking every pair
+            let brute_force = expanded_galaxies
+                .iter()
+                .enumerate()
+                .map(|(k, (x0, y0))| {
+                    expanded_galaxies
+                        .iter()
+                        .enumerate()
+                        .filter(|(l, _)| *l != k)
+                        .map(|(_, (x1, y1))| x0.abs_diff(*x1) + y0.abs_diff(*y1))
+                        .min()
+                })
+                .collect::<Vec<_>>();
+
+            assert_eq!(brute_force, nearest_galaxy_distances(&expanded_galaxies));
+        }
+
+        let distances = day_11_nearest_galaxy_distances(EXAMPLE, 2).unwrap();
+        assert_eq!(9, distances.len());
+        assert_eq!(((0, 3), Some(6)), distances[0]);
+        assert_eq!(
+            Ok(vec![((0, 0), None)]),
+            day_11_nearest_galaxy_distances("#..", 2)
+        );
+        assert_eq!(
+            Err(ZeroExpansionError),
+            day_11_nearest_galaxy_distances(EXAMPLE, 0)
+        );
+    }
 }
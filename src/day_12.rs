@@ -1,170 +1,439 @@
 use wasm_bindgen::prelude::*;
 
+use std::convert::TryInto;
+use std::fmt;
 use std::iter;
 
-fn is_valid(
-    condition: &str,
-    contiguous_groups_of_damaged_springs: &Vec<usize>,
-    complete: bool,
-) -> bool {
-    let mut prev = None;
-    let mut group_size = 0;
-
-    let contiguous_groups_of_damaged_springs_clone = contiguous_groups_of_damaged_springs.clone();
-    let mut group_iterator = contiguous_groups_of_damaged_springs_clone.iter();
-    let mut next_group_to_match = match group_iterator.next() {
-        Some(size) => *size,
-        None => 0,
-    };
-
-    for c in condition.chars() {
-        match c {
-            '#' => {
-                group_size = group_size + 1;
-                if group_size > next_group_to_match {
-                    return false;
-                }
-            }
-            '.' => {
-                if let Some(prev) = prev {
-                    if prev == '#' {
-                        if group_size != next_group_to_match {
-                            return false;
-                        }
+use crate::input::Input;
+use crate::streaming::LineSolver;
+
+// None unless the line is springs, each #, . or ?, followed by comma separated group sizes
+fn parse_line(line: &str) -> Option<(&str, Vec<usize>)> {
+    let mut line_iterator = line.split_whitespace();
+    let conditions = line_iterator
+        .next()
+        .filter(|conditions| conditions.chars().all(|c| matches!(c, '#' | '.' | '?')))?;
+    let groups = line_iterator
+        .next()?
+        .split(",")
+        .map(|x| x.parse::<usize>().ok())
+        .collect::<Option<_>>()?;
+
+    Some((conditions, groups))
+}
+
+#[derive(Debug, PartialEq)]
+pub struct InvalidRecordError {
+    pub record: String,
+}
+
+impl fmt::Display for InvalidRecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid condition record {}", self.record)
+    }
+}
+
+// Counts arrangements by dynamic programming over the springs, tracking the number of partial arrangements
+// for each (group index, length of the current run of damaged springs) after each position.
+fn count_arrangements(conditions: &str, groups: &[usize]) -> u128 {
+    let n_groups = groups.len();
+    let max_run = groups.iter().copied().max().unwrap_or(0);
 
-                        // End of a contiguous group, consume one group for checking
-                        next_group_to_match = match group_iterator.next() {
-                            Some(size) => *size,
-                            None => 0,
-                        };
+    let mut dp = vec![vec![0u128; max_run + 1]; n_groups + 1];
+    dp[0][0] = 1;
 
-                        group_size = 0;
+    for c in conditions.chars() {
+        let (damaged, operational) = match c {
+            '#' => (true, false),
+            '.' => (false, true),
+            '?' => (true, true),
+            _ => unreachable!(),
+        };
+
+        let mut next = vec![vec![0u128; max_run + 1]; n_groups + 1];
+        for (g, runs) in dp.iter().enumerate() {
+            for (run, count) in runs.iter().enumerate() {
+                if *count == 0 {
+                    continue;
+                }
+
+                if damaged {
+                    // Damaged: extend the current group if it has room
+                    if g < n_groups && run < groups[g] {
+                        next[g][run + 1] += count;
+                    }
+                }
+                if operational {
+                    // Operational: either between groups, or closing a group which is exactly full
+                    if run == 0 {
+                        next[g][0] += count;
+                    } else if run == groups[g] {
+                        next[g + 1][0] += count;
                     }
                 }
             }
-            '?' => {
-                // Hitting a question mark will also trigger an early return
-                break;
-            }
-            _ => unreachable!(),
         }
+        dp = next;
+    }
 
-        prev = Some(c);
+    // Either every group has been closed, or the last group runs to the end of the record
+    let mut total = dp[n_groups][0];
+    if n_groups > 0 {
+        total += dp[n_groups - 1][groups[n_groups - 1]];
     }
+    total
+}
+
+// Repeats the conditions `factor` times separated by unknown springs, and the groups `factor` times
+fn unfold(conditions: &str, groups: &[usize], factor: usize) -> (String, Vec<usize>) {
+    let new_conditions = vec![conditions; factor].join("?");
+    let new_groups = groups.repeat(factor);
 
-    // Will return true if the conditions have been fully consumed even if there are groups remaining.
-    // This allows it to parse and reject half filled conditions for early return
+    (new_conditions, new_groups)
+}
 
-    // If the whole thing must be correct, check that the remaining conditions are fulfilled
-    // There should be no more groups to match, i.e. the iterator should have been fully consumed
-    // If it ended on a #, the group_size should match the next group to be matched
-    // If it ended on a ., the group_size should be 0 and the group size to match should be 0 (will be set to 0 when the iterator is fully consumed)
-    let next_group = group_iterator.next();
-    if complete && (!next_group.is_none() || group_size != next_group_to_match) {
-        return false;
+pub fn day_12_sum_of_arrangements(
+    condition_records: &str,
+    unfold_factor: usize,
+) -> Result<u128, InvalidRecordError> {
+    let mut acc = 0;
+    let condition_records = Input::new(condition_records);
+    for line in condition_records.lines() {
+        let (conditions, groups) = parse_line(line).ok_or_else(|| InvalidRecordError {
+            record: line.to_string(),
+        })?;
+        let (conditions, groups) = unfold(conditions, &groups, unfold_factor);
+        acc += count_arrangements(&conditions, &groups);
     }
-    true
+
+    Ok(acc)
 }
 
-fn replace_char_at_position(s: &str, i: usize, replacement: char) -> String {
-    let mut new_string = String::from(s);
-    new_string.replace_range(
-        new_string
-            .char_indices()
-            .nth(i)
-            .map(|(pos, ch)| (pos..pos + ch.len_utf8()))
-            .unwrap(),
-        &replacement.to_string(),
-    );
-    new_string
-}
-
-fn find_different_arrangements(
-    condition: &str,
-    contiguous_groups_of_damaged_springs: &Vec<usize>,
-    pos: usize,
-) -> usize {
-    if !is_valid(condition, contiguous_groups_of_damaged_springs, false) {
-        return 0;
-    }
-
-    for (i, c) in condition.chars().enumerate() {
-        if i < pos {
-            continue;
-        }
-        match c {
-            '.' | '#' => continue,
-            '?' => {
-                return find_different_arrangements(
-                    &replace_char_at_position(condition, i, '.'),
-                    contiguous_groups_of_damaged_springs,
-                    i,
-                ) + find_different_arrangements(
-                    &replace_char_at_position(condition, i, '#'),
-                    contiguous_groups_of_damaged_springs,
-                    i,
-                );
+// State after placing a spring, given the group index and the length of the current run of damaged springs
+fn place_spring(
+    spring: char,
+    (g, run): (usize, usize),
+    groups: &[usize],
+) -> Option<(usize, usize)> {
+    match spring {
+        '#' if g < groups.len() && run < groups[g] => Some((g, run + 1)),
+        '.' if run == 0 => Some((g, 0)),
+        '.' if g < groups.len() && run == groups[g] => Some((g + 1, 0)),
+        _ => None,
+    }
+}
+
+// Every arrangement of a condition record, ordered lexicographically with damaged (#) before operational (.) springs.
+// Arrangements are built one at a time from the number of ways to complete the record from each state.
+pub struct Arrangements {
+    conditions: Vec<char>,
+    groups: Vec<usize>,
+    // ways[pos][g][run]: number of ways to fill conditions[pos..] given the state before pos
+    ways: Vec<Vec<Vec<u128>>>,
+}
+
+impl Arrangements {
+    // The conditions must only be #, . and ?, as checked by parse_line
+    fn new(conditions: &str, groups: &[usize]) -> Arrangements {
+        let conditions = conditions.chars().collect::<Vec<_>>();
+        let n_groups = groups.len();
+        let max_run = groups.iter().copied().max().unwrap_or(0);
+
+        let mut ways = vec![vec![vec![0u128; max_run + 1]; n_groups + 1]; conditions.len() + 1];
+        ways[conditions.len()][n_groups][0] = 1;
+        if n_groups > 0 {
+            ways[conditions.len()][n_groups - 1][groups[n_groups - 1]] = 1;
+        }
+
+        for pos in (0..conditions.len()).rev() {
+            let springs: &[char] = match conditions[pos] {
+                '#' => &['#'],
+                '.' => &['.'],
+                '?' => &['#', '.'],
+                _ => unreachable!(),
+            };
+            for g in 0..=n_groups {
+                for run in 0..=max_run {
+                    ways[pos][g][run] = springs
+                        .iter()
+                        .filter_map(|spring| place_spring(*spring, (g, run), groups))
+                        .map(|(g, run)| ways[pos + 1][g][run])
+                        .sum();
+                }
             }
-            _ => unreachable!(),
+        }
+
+        Arrangements {
+            conditions,
+            groups: groups.to_vec(),
+            ways,
         }
     }
 
-    if is_valid(condition, contiguous_groups_of_damaged_springs, true) {
-        return 1;
+    pub fn count(&self) -> u128 {
+        self.ways[0][0][0]
     }
 
-    0
+    // The k-th arrangement in lexicographic order, starting from 0
+    pub fn nth(&self, mut k: u128) -> Option<String> {
+        if k >= self.count() {
+            return None;
+        }
+
+        let mut arrangement = String::with_capacity(self.conditions.len());
+        let mut state = (0, 0);
+        for (pos, condition) in self.conditions.iter().enumerate() {
+            for spring in ['#', '.'] {
+                if *condition != '?' && *condition != spring {
+                    continue;
+                }
+                if let Some((g, run)) = place_spring(spring, state, &self.groups) {
+                    let ways = self.ways[pos + 1][g][run];
+                    if k < ways {
+                        arrangement.push(spring);
+                        state = (g, run);
+                        break;
+                    }
+                    k -= ways;
+                }
+            }
+        }
+
+        Some(arrangement)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = String> + '_ {
+        let mut k = 0;
+        iter::from_fn(move || {
+            let arrangement = self.nth(k);
+            k += 1;
+            arrangement
+        })
+    }
+
+    // Picks an arrangement uniformly at random, given a source of random u64s
+    pub fn sample<F: FnMut() -> u64>(&self, mut next_u64: F) -> Option<String> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+
+        // Reject the lowest values so that every remainder is equally likely
+        let threshold = count.wrapping_neg() % count;
+        loop {
+            let r = ((next_u64() as u128) << 64) | next_u64() as u128;
+            if r >= threshold {
+                return self.nth(r % count);
+            }
+        }
+    }
 }
 
-fn parse_line(line: &str) -> (&str, Vec<usize>) {
-    let mut line_iterator = line.split_whitespace();
-    let conditions = line_iterator.next().unwrap();
-    let groups = line_iterator
-        .next()
-        .unwrap()
-        .split(",")
-        .map(|x| x.parse::<usize>().unwrap())
-        .collect();
+pub fn day_12_arrangements(
+    line: &str,
+    unfold_factor: usize,
+) -> Result<Arrangements, InvalidRecordError> {
+    let (conditions, groups) = parse_line(line.trim()).ok_or_else(|| InvalidRecordError {
+        record: line.trim().to_string(),
+    })?;
+    let (conditions, groups) = unfold(conditions, &groups, unfold_factor);
 
-    (conditions, groups)
+    Ok(Arrangements::new(&conditions, &groups))
 }
 
-#[wasm_bindgen]
-pub fn day_12_sum_of_possible_arrangements(condition_records: &str) -> usize {
-    let mut acc = 0;
-    for line in condition_records.split("\n").map(|line| line.trim()) {
-        let (conditions, groups) = parse_line(line);
-        acc = acc + find_different_arrangements(conditions, &groups, 0);
-    }
+// Samples an arrangement using a SplitMix64 generator seeded with `seed`
+pub fn day_12_sample_arrangement(
+    line: &str,
+    unfold_factor: usize,
+    seed: u64,
+) -> Result<Option<String>, InvalidRecordError> {
+    let mut state = seed;
+    Ok(day_12_arrangements(line, unfold_factor)?.sample(|| {
+        state = state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }))
+}
 
-    acc
+// JavaScript gets a BigInt, or an exception if a record is invalid or the sum needs more than 64 bits
+fn sum_of_arrangements_for_js(
+    condition_records: &str,
+    unfold_factor: usize,
+) -> Result<u64, JsValue> {
+    day_12_sum_of_arrangements(condition_records, unfold_factor)
+        .map_err(|error| JsValue::from_str(&error.to_string()))?
+        .try_into()
+        .map_err(|_| JsValue::from_str("Sum of arrangements does not fit in 64 bits"))
 }
 
 #[wasm_bindgen]
-pub fn day_12_sum_of_possible_arrangements_part_2(condition_records: &str) -> usize {
-    let mut acc = 0;
-    for line in condition_records.split("\n").map(|line| line.trim()) {
-        let (conditions, groups) = parse_line(line);
-        let new_conditions = iter::repeat(conditions)
-            .take(5)
-            .collect::<Vec<&str>>()
-            .join("?");
-        let mut new_groups = groups.clone();
-        for _ in 1..5 {
-            new_groups.append(&mut groups.clone());
+pub fn day_12_sum_of_possible_arrangements(condition_records: &str) -> Result<u64, JsValue> {
+    sum_of_arrangements_for_js(condition_records, 1)
+}
+
+#[wasm_bindgen]
+pub fn day_12_sum_of_possible_arrangements_part_2(condition_records: &str) -> Result<u64, JsValue> {
+    sum_of_arrangements_for_js(condition_records, 5)
+}
+
+// Running sum of the arrangements of condition records read a line at a time
+#[derive(Debug)]
+pub struct ArrangementSum {
+    unfold_factor: usize,
+    total: u128,
+}
+
+impl ArrangementSum {
+    pub fn new(unfold_factor: usize) -> ArrangementSum {
+        ArrangementSum {
+            unfold_factor,
+            total: 0,
         }
-        let a = find_different_arrangements(&new_conditions, &new_groups, 0);
-        dbg!(a);
-        acc = acc + a;
     }
+}
+
+impl LineSolver for ArrangementSum {
+    type Total = u128;
+    type Error = InvalidRecordError;
 
-    acc
+    fn feed(&mut self, line: &str) -> Result<(), InvalidRecordError> {
+        let (conditions, groups) = parse_line(line).ok_or_else(|| InvalidRecordError {
+            record: line.to_string(),
+        })?;
+        let (conditions, groups) = unfold(conditions, &groups, self.unfold_factor);
+        self.total += count_arrangements(&conditions, &groups);
+        Ok(())
+    }
+
+    fn total(&self) -> u128 {
+        self.total
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn is_valid(
+        condition: &str,
+        contiguous_groups_of_damaged_springs: &Vec<usize>,
+        complete: bool,
+    ) -> bool {
+        let mut prev = None;
+        let mut group_size = 0;
+
+        let contiguous_groups_of_damaged_springs_clone =
+            contiguous_groups_of_damaged_springs.clone();
+        let mut group_iterator = contiguous_groups_of_damaged_springs_clone.iter();
+        let mut next_group_to_match = match group_iterator.next() {
+            Some(size) => *size,
+            None => 0,
+        };
+
+        for c in condition.chars() {
+            match c {
+                '#' => {
+                    group_size = group_size + 1;
+                    if group_size > next_group_to_match {
+                        return false;
+                    }
+                }
+                '.' => {
+                    if let Some(prev) = prev {
+                        if prev == '#' {
+                            if group_size != next_group_to_match {
+                                return false;
+                            }
+
+                            // End of a contiguous group, consume one group for checking
+                            next_group_to_match = match group_iterator.next() {
+                                Some(size) => *size,
+                                None => 0,
+                            };
+
+                            group_size = 0;
+                        }
+                    }
+                }
+                '?' => {
+                    // Hitting a question mark will also trigger an early return
+                    break;
+                }
+                _ => unreachable!(),
+            }
+
+            prev = Some(c);
+        }
+
+        // Will return true if the conditions have been fully consumed even if there are groups remaining.
+        // This allows it to parse and reject half filled conditions for early return
+
+        // If the whole thing must be correct, check that the remaining conditions are fulfilled
+        // There should be no more groups to match, i.e. the iterator should have been fully consumed
+        // If it ended on a #, the group_size should match the next group to be matched
+        // If it ended on a ., the group_size should be 0 and the group size to match should be 0 (will be set to 0 when the iterator is fully consumed)
+        let next_group = group_iterator.next();
+        if complete && (!next_group.is_none() || group_size != next_group_to_match) {
+            return false;
+        }
+        true
+    }
+
+    fn replace_char_at_position(s: &str, i: usize, replacement: char) -> String {
+        let mut new_string = String::from(s);
+        new_string.replace_range(
+            new_string
+                .char_indices()
+                .nth(i)
+                .map(|(pos, ch)| pos..pos + ch.len_utf8())
+                .unwrap(),
+            &replacement.to_string(),
+        );
+        new_string
+    }
+
+    // Brute force search over every unknown spring, kept to cross check count_arrangements
+    fn find_different_arrangements(
+        condition: &str,
+        contiguous_groups_of_damaged_springs: &Vec<usize>,
+        pos: usize,
+    ) -> usize {
+        if !is_valid(condition, contiguous_groups_of_damaged_springs, false) {
+            return 0;
+        }
+
+        for (i, c) in condition.chars().enumerate() {
+            if i < pos {
+                continue;
+            }
+            match c {
+                '.' | '#' => continue,
+                '?' => {
+                    return find_different_arrangements(
+                        &replace_char_at_position(condition, i, '.'),
+                        contiguous_groups_of_damaged_springs,
+                        i,
+                    ) + find_different_arrangements(
+                        &replace_char_at_position(condition, i, '#'),
+                        contiguous_groups_of_damaged_springs,
+                        i,
+                    );
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        if is_valid(condition, contiguous_groups_of_damaged_springs, true) {
+            return 1;
+        }
+
+        0
+    }
+
+    use crate::streaming::{final_total, running_totals};
+
     macro_rules! generate_is_valid_tests {
         ($($name:ident: $value:expr,)*) => {
         $(
@@ -230,8 +499,6 @@ mod tests {
         );
     }
 
-    // Takes a while to run
-    #[ignore]
     #[test]
     fn test_find_different_arrangements_part_2() {
         assert_eq!(
@@ -244,6 +511,182 @@ mod tests {
         ????.######..#####. 1,6,5
         ?###???????? 3,2,1"#
             )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_count_arrangements() {
+        for (conditions, groups) in [
+            ("???.###", vec![1, 1, 3]),
+            (".??..??...?##.", vec![1, 1, 3]),
+            ("?#?#?#?#?#?#?#?", vec![1, 3, 1, 6]),
+            ("????.#...#...", vec![4, 1, 1]),
+            ("????.######..#####.", vec![1, 6, 5]),
+            ("?###????????", vec![3, 2, 1]),
+            ("?????", vec![]),
+            ("..#..", vec![]),
+        ] {
+            assert_eq!(
+                find_different_arrangements(conditions, &groups, 0) as u128,
+                count_arrangements(conditions, &groups)
+            );
+        }
+
+        assert_eq!(
+            506250,
+            count_arrangements(
+                &unfold("?###????????", &[3, 2, 1], 5).0,
+                &[3, 2, 1].repeat(5)
+            )
+        );
+    }
+
+    #[test]
+    fn test_day_12_sum_of_arrangements() {
+        const EXAMPLE: &str = r#"???.### 1,1,3
+        .??..??...?##. 1,1,3
+        ?#?#?#?#?#?#?#? 1,3,1,6
+        ????.#...#... 4,1,1
+        ????.######..#####. 1,6,5
+        ?###???????? 3,2,1"#;
+
+        assert_eq!(Ok(21), day_12_sum_of_arrangements(EXAMPLE, 1));
+        assert_eq!(
+            Ok(21),
+            day_12_sum_of_arrangements(&format!("{}\n", EXAMPLE), 1)
+        );
+        assert_eq!(Ok(525152), day_12_sum_of_arrangements(EXAMPLE, 5));
+        // Larger unfoldings no longer fit in a u64
+        assert!(day_12_sum_of_arrangements(EXAMPLE, 20).unwrap() > u64::MAX as u128);
+        assert_eq!(
+            Err(InvalidRecordError {
+                record: "???.### 1,x".to_string()
+            }),
+            day_12_sum_of_arrangements("#.# 1,1\n???.### 1,x", 1)
+        );
+        assert_eq!(
+            Err(InvalidRecordError {
+                record: "a?# 1".to_string()
+            }),
+            day_12_sum_of_arrangements("a?# 1", 1)
+        );
+    }
+
+    #[test]
+    fn test_arrangements_iter() {
+        assert_eq!(
+            vec!["#.#.###"],
+            day_12_arrangements("???.### 1,1,3", 1)
+                .unwrap()
+                .iter()
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![
+                ".#...#....###.",
+                ".#....#...###.",
+                "..#..#....###.",
+                "..#...#...###.",
+            ],
+            day_12_arrangements(".??..??...?##. 1,1,3", 1)
+                .unwrap()
+                .iter()
+                .collect::<Vec<_>>()
+        );
+
+        let groups = vec![3, 2, 1];
+        let arrangements = Arrangements::new("?###????????", &groups);
+        let all = arrangements.iter().collect::<Vec<_>>();
+        assert_eq!(10, all.len());
+        assert!(all.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(all
+            .iter()
+            .all(|arrangement| is_valid(arrangement, &groups, true)));
+        assert_eq!(None, arrangements.nth(10));
+
+        assert!(day_12_arrangements("???.### 1,,3", 1).is_err());
+    }
+
+    #[test]
+    fn test_arrangements_count() {
+        for line in [
+            "???.### 1,1,3",
+            "?#?#?#?#?#?#?#? 1,3,1,6",
+            "????.######..#####. 1,6,5",
+            "?###???????? 3,2,1",
+            "#.# 2",
+        ] {
+            let (conditions, groups) = parse_line(line).unwrap();
+            for factor in 1..=5 {
+                let (conditions, groups) = unfold(conditions, &groups, factor);
+                assert_eq!(
+                    count_arrangements(&conditions, &groups),
+                    Arrangements::new(&conditions, &groups).count()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_day_12_sample_arrangement() {
+        let line = "?###???????? 3,2,1";
+
+        // Sampling from billions of arrangements only needs the counts
+        let arrangements = day_12_arrangements(line, 10).unwrap();
+        assert!(arrangements.count() > 1_000_000_000);
+        let (_, groups) = unfold("?###????????", &[3, 2, 1], 10);
+        for seed in 0..10 {
+            let sample = day_12_sample_arrangement(line, 10, seed).unwrap().unwrap();
+            assert!(is_valid(&sample, &groups, true));
+        }
+
+        // Every arrangement is drawn with roughly equal frequency
+        let mut frequencies = std::collections::HashMap::new();
+        for seed in 0..2000 {
+            *frequencies
+                .entry(day_12_sample_arrangement(line, 1, seed).unwrap().unwrap())
+                .or_insert(0) += 1;
+        }
+        assert_eq!(10, frequencies.len());
+        assert!(frequencies.values().all(|f| (120..=280).contains(f)));
+
+        assert_eq!(Ok(None), day_12_sample_arrangement("#.# 2", 1, 0));
+        assert_eq!(
+            Err(InvalidRecordError {
+                record: "#.#".to_string()
+            }),
+            day_12_sample_arrangement(" #.# ", 1, 0)
+        );
+        assert_eq!(
+            Err(InvalidRecordError {
+                record: "x?# 1".to_string()
+            }),
+            day_12_sample_arrangement("x?# 1", 1, 0)
+        );
+        assert_eq!(
+            Some(InvalidRecordError {
+                record: "x?# 1".to_string()
+            }),
+            day_12_arrangements("x?# 1", 1).err()
+        );
+    }
+
+    #[test]
+    fn test_arrangement_sum() {
+        let records = "???.### 1,1,3\n.??..??...?##. 1,1,3\n";
+        assert_eq!(
+            vec![1, 5],
+            running_totals(records.as_bytes(), ArrangementSum::new(1))
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        );
+        assert_eq!(
+            Some(1 + 16384),
+            final_total(records.as_bytes(), ArrangementSum::new(5)).unwrap()
         );
+        assert!(final_total("???.### 1,x".as_bytes(), ArrangementSum::new(1)).is_err());
+        assert!(final_total("???.###".as_bytes(), ArrangementSum::new(1)).is_err());
+        assert!(final_total("a?# 1".as_bytes(), ArrangementSum::new(1)).is_err());
     }
 }
@@ -1,166 +1,214 @@
-#[derive(Debug, PartialEq)]
-enum Reflection {
+use std::fmt;
+
+use crate::input::Input;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Reflection {
     Vertical(usize),
     Horizontal(usize),
 }
 
-fn parse_notes(notes: &str) -> Vec<Vec<Vec<char>>> {
-    let mut patterns: Vec<Vec<Vec<char>>> = Vec::new();
-
-    let mut pattern: Vec<Vec<char>> = Vec::new();
-    for line in notes.split("\n").map(|line| line.trim()) {
-        if line == "" {
-            patterns.push(pattern);
-            pattern = Vec::new();
-            continue;
-        }
-        let mut row = Vec::new();
-        for c in line.chars() {
-            row.push(c);
-        }
-        pattern.push(row);
-    }
-    if pattern.len() != 0 {
-        patterns.push(pattern);
-    }
+#[derive(Debug, PartialEq)]
+pub struct ReflectionLine {
+    pub reflection: Reflection,
+    // Pairs of mirrored cells which differ, as ((row, column), (row, column))
+    pub mismatches: Vec<((usize, usize), (usize, usize))>,
+}
 
-    patterns
+fn parse_notes(notes: &str) -> Vec<Vec<Vec<char>>> {
+    Input::new(notes)
+        .sections()
+        .map(|pattern| {
+            pattern
+                .split('\n')
+                .map(|row| row.chars().collect())
+                .collect()
+        })
+        .collect()
 }
 
-fn find_lines_of_reflection(pattern: &Vec<Vec<char>>) -> Vec<Reflection> {
+// Every line of reflection with exactly `tolerance` mismatched pairs of cells.
+// Each candidate line is checked in a single pass, stopping as soon as it has too many mismatches.
+fn find_reflections(pattern: &[Vec<char>], tolerance: usize) -> Vec<ReflectionLine> {
     let mut lines = Vec::new();
 
     let n = pattern.len();
+    let m = pattern[0].len();
+
     'iterate_rows: for i in 1..n {
-        let mut pairs = Vec::new();
-        // Generates pairs of indices around the split
-        if i < n / 2 + 1 {
-            for j in 0..i {
-                pairs.push((i - (j + 1), i + (j)));
-            }
-        } else {
-            for j in i..n {
-                let diff = j - i;
-                pairs.push((i - (diff + 1), i + diff));
-            }
-        }
-        // All pairs must be mirrored
-        for (l, r) in pairs {
-            if pattern[l] != pattern[r] {
-                continue 'iterate_rows;
+        let mut mismatches = Vec::new();
+        // Pairs of rows around the split, up to the nearest edge
+        for d in 0..i.min(n - i) {
+            let (l, r) = (i - (d + 1), i + d);
+            for (j, (a, b)) in pattern[l].iter().zip(&pattern[r]).enumerate() {
+                if a != b {
+                    if mismatches.len() == tolerance {
+                        continue 'iterate_rows;
+                    }
+                    mismatches.push(((l, j), (r, j)));
+                }
             }
         }
 
-        lines.push(Reflection::Horizontal(i));
+        if mismatches.len() == tolerance {
+            lines.push(ReflectionLine {
+                reflection: Reflection::Horizontal(i),
+                mismatches,
+            });
+        }
     }
 
-    let rotated_pattern = rotate_pattern(pattern);
-    let m = rotated_pattern.len();
-    'iterate_cols: for i in 1..m {
-        let mut pairs = Vec::new();
-        if i < m / 2 + 1 {
-            for j in 0..i {
-                pairs.push((i - (j + 1), i + (j)));
-            }
-        } else {
-            for j in i..m {
-                let diff = j - i;
-                pairs.push((i - (diff + 1), i + diff));
-            }
-        }
-        for (l, r) in pairs {
-            if rotated_pattern[l] != rotated_pattern[r] {
-                continue 'iterate_cols;
+    'iterate_cols: for j in 1..m {
+        let mut mismatches = Vec::new();
+        for d in 0..j.min(m - j) {
+            let (l, r) = (j - (d + 1), j + d);
+            for (i, row) in pattern.iter().enumerate() {
+                if row[l] != row[r] {
+                    if mismatches.len() == tolerance {
+                        continue 'iterate_cols;
+                    }
+                    mismatches.push(((i, l), (i, r)));
+                }
             }
         }
 
-        lines.push(Reflection::Vertical(i));
+        if mismatches.len() == tolerance {
+            lines.push(ReflectionLine {
+                reflection: Reflection::Vertical(j),
+                mismatches,
+            });
+        }
     }
 
     lines
 }
 
-fn rotate_pattern(pattern: &Vec<Vec<char>>) -> Vec<Vec<char>> {
-    let m = pattern[0].len();
-
-    let mut rotated_pattern = vec![Vec::new(); m];
-    for row in pattern {
-        for (i, c) in row.iter().enumerate() {
-            rotated_pattern[i].push(*c);
-        }
+fn summarize(reflection: &Reflection) -> usize {
+    match reflection {
+        Reflection::Vertical(i) => *i,
+        Reflection::Horizontal(i) => i * 100,
     }
+}
 
-    rotated_pattern
+// Lines of reflection of each pattern in the notes, allowing exactly `tolerance` smudges
+pub fn day_13_find_reflections(notes: &str, tolerance: usize) -> Vec<Vec<ReflectionLine>> {
+    parse_notes(notes)
+        .iter()
+        .map(|pattern| find_reflections(pattern, tolerance))
+        .collect()
 }
 
-pub fn day_13_summarize_notes(notes: &str) -> usize {
-    let mut acc = 0;
+#[derive(Debug, PartialEq)]
+pub struct PatternSummary {
+    pub index: usize,
+    pub reflection: Reflection,
+    pub contribution: usize,
+}
 
-    let patterns = parse_notes(notes);
+// A pattern without exactly one line of reflection
+#[derive(Debug, PartialEq)]
+pub struct ReflectionCountError {
+    pub index: usize,
+    pub reflections: Vec<Reflection>,
+}
 
-    for pattern in patterns {
-        // There is guaranteed to be only one line for the original pattern
-        // From my experience
-        match find_lines_of_reflection(&pattern)[0] {
-            Reflection::Vertical(i) => {
-                acc = acc + i;
-            }
-            Reflection::Horizontal(i) => {
-                acc = acc + (i * 100);
-            }
+impl fmt::Display for ReflectionCountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Pattern {} has {} lines of reflection, expected exactly one: {:?}",
+            self.index,
+            self.reflections.len(),
+            self.reflections
+        )
+    }
+}
+
+fn summarize_patterns(
+    patterns: &[Vec<Vec<char>>],
+    tolerance: usize,
+) -> Result<Vec<PatternSummary>, ReflectionCountError> {
+    let mut summaries = Vec::new();
+
+    for (index, pattern) in patterns.iter().enumerate() {
+        let reflections = find_reflections(pattern, tolerance)
+            .iter()
+            .map(|line| line.reflection)
+            .collect::<Vec<_>>();
+        if reflections.len() != 1 {
+            return Err(ReflectionCountError { index, reflections });
         }
+
+        summaries.push(PatternSummary {
+            index,
+            reflection: reflections[0],
+            contribution: summarize(&reflections[0]),
+        });
     }
 
-    acc
+    Ok(summaries)
 }
 
-pub fn day_13_summarize_notes_part_2(notes: &str) -> usize {
-    let mut acc = 0;
-
-    let patterns = parse_notes(notes);
+// Draws the line of reflection into the pattern between the mirrored rows or columns
+fn render_reflection(pattern: &[Vec<char>], reflection: &Reflection) -> String {
+    let mut ret = String::new();
 
-    'outer: for pattern in patterns {
-        let original_line_of_reflection = &find_lines_of_reflection(&pattern)[0];
+    for (i, row) in pattern.iter().enumerate() {
+        if *reflection == Reflection::Horizontal(i) {
+            ret.push_str(&"-".repeat(row.len()));
+            ret.push('\n');
+        }
+        for (j, c) in row.iter().enumerate() {
+            if *reflection == Reflection::Vertical(j) {
+                ret.push('|');
+            }
+            ret.push(*c);
+        }
+        ret.push('\n');
+    }
 
-        let n = pattern.len();
-        let m = pattern[0].len();
+    ret
+}
 
-        for i in 0..n {
-            for j in 0..m {
-                let mut new_pattern = pattern.clone();
-                new_pattern[i][j] = match new_pattern[i][j] {
-                    '#' => '.',
-                    '.' => '#',
-                    _ => unreachable!(),
-                };
+pub fn day_13_summarize_patterns(
+    notes: &str,
+    tolerance: usize,
+) -> Result<Vec<PatternSummary>, ReflectionCountError> {
+    summarize_patterns(&parse_notes(notes), tolerance)
+}
 
-                let lines_of_reflection = find_lines_of_reflection(&new_pattern);
+// Every pattern with its line of reflection drawn in, separated by blank lines
+pub fn day_13_render_reflections(
+    notes: &str,
+    tolerance: usize,
+) -> Result<String, ReflectionCountError> {
+    let patterns = parse_notes(notes);
 
-                // There can be multiple lines of reflection in the new pattern
-                for line in lines_of_reflection {
-                    // A different reflection line must be valid
-                    if line == *original_line_of_reflection {
-                        continue;
-                    }
+    Ok(summarize_patterns(&patterns, tolerance)?
+        .iter()
+        .map(|summary| render_reflection(&patterns[summary.index], &summary.reflection))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
 
-                    match line {
-                        Reflection::Vertical(x) => {
-                            acc = acc + x;
-                            continue 'outer;
-                        }
-                        Reflection::Horizontal(x) => {
-                            acc = acc + (x * 100);
-                            continue 'outer;
-                        }
-                    }
-                }
-            }
-        }
-        // All corrected patterns should have a different valid reflection line
-        unreachable!();
-    }
+pub fn day_13_summarize_notes(notes: &str) -> usize {
+    // There is guaranteed to be only one line for the original pattern
+    // From my experience
+    day_13_summarize_patterns(notes, 0)
+        .unwrap()
+        .iter()
+        .map(|summary| summary.contribution)
+        .sum()
+}
 
-    acc
+pub fn day_13_summarize_notes_part_2(notes: &str) -> usize {
+    // Fixing the smudge leaves exactly one mismatched pair of cells across the new line of reflection.
+    // The original line has no mismatches, so it cannot be picked again.
+    day_13_summarize_patterns(notes, 1)
+        .unwrap()
+        .iter()
+        .map(|summary| summary.contribution)
+        .sum()
 }
 
 #[cfg(test)]
@@ -222,11 +270,11 @@ mod tests {
 
         assert_eq!(
             Reflection::Vertical(5),
-            find_lines_of_reflection(&patterns[0])[0]
+            find_reflections(&patterns[0], 0)[0].reflection
         );
         assert_eq!(
             Reflection::Horizontal(4),
-            find_lines_of_reflection(&patterns[1])[0]
+            find_reflections(&patterns[1], 0)[0].reflection
         );
     }
 
@@ -244,11 +292,11 @@ mod tests {
 
         assert_eq!(
             Reflection::Vertical(1),
-            find_lines_of_reflection(&patterns[0])[0]
+            find_reflections(&patterns[0], 0)[0].reflection
         );
         assert_eq!(
             Reflection::Vertical(10),
-            find_lines_of_reflection(&patterns[0])[1]
+            find_reflections(&patterns[0], 0)[1].reflection
         );
     }
 
@@ -263,4 +311,103 @@ mod tests {
         assert_eq!(400, day_13_summarize_notes_part_2(EXAMPLE));
         assert_eq!(1400, day_13_summarize_notes_part_2(ADDITIONAL_EXAMPLE));
     }
+
+    #[test]
+    fn test_find_reflections() {
+        let patterns = parse_notes(EXAMPLE);
+
+        assert_eq!(
+            vec![ReflectionLine {
+                reflection: Reflection::Horizontal(3),
+                mismatches: vec![((0, 0), (5, 0))]
+            }],
+            find_reflections(&patterns[0], 1)
+        );
+        assert_eq!(
+            vec![ReflectionLine {
+                reflection: Reflection::Horizontal(1),
+                mismatches: vec![((0, 4), (1, 4))]
+            }],
+            find_reflections(&patterns[1], 1)
+        );
+
+        assert_eq!(
+            vec![
+                vec![Reflection::Vertical(5)],
+                vec![Reflection::Horizontal(4)]
+            ],
+            day_13_find_reflections(EXAMPLE, 0)
+                .iter()
+                .map(|lines| lines.iter().map(|line| line.reflection).collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        );
+
+        // Every line with no more than k mismatches is found with some tolerance up to k
+        for pattern in parse_notes(ADDITIONAL_EXAMPLE) {
+            let n = pattern.len() - 1 + pattern[0].len() - 1;
+            let lines = (0..=pattern.len() * pattern[0].len())
+                .map(|k| find_reflections(&pattern, k).len())
+                .sum::<usize>();
+            assert_eq!(n, lines);
+        }
+    }
+
+    #[test]
+    fn test_day_13_summarize_patterns() {
+        assert_eq!(
+            Ok(vec![
+                PatternSummary {
+                    index: 0,
+                    reflection: Reflection::Vertical(5),
+                    contribution: 5
+                },
+                PatternSummary {
+                    index: 1,
+                    reflection: Reflection::Horizontal(4),
+                    contribution: 400
+                }
+            ]),
+            day_13_summarize_patterns(EXAMPLE, 0)
+        );
+
+        assert_eq!(
+            Err(ReflectionCountError {
+                index: 1,
+                reflections: vec![]
+            }),
+            day_13_summarize_patterns("#.\n#.\n\n#.\n..", 0)
+        );
+        assert_eq!(
+            Err(ReflectionCountError {
+                index: 0,
+                reflections: vec![Reflection::Horizontal(1), Reflection::Vertical(1)]
+            }),
+            day_13_summarize_patterns("##\n##\n\n#.\n..", 0)
+        );
+    }
+
+    #[test]
+    fn test_day_13_render_reflections() {
+        assert_eq!(
+            Ok(r#"#.##.|.##.
+..#.#|#.#.
+##...|...#
+##...|...#
+..#.#|#.#.
+..##.|.##.
+#.#.#|#.#.
+
+#...##..#
+#....#..#
+..##..###
+#####.##.
+---------
+#####.##.
+..##..###
+#....#..#
+"#
+            .to_string()),
+            day_13_render_reflections(EXAMPLE, 0)
+        );
+    }
 }
@@ -1,332 +1,372 @@
-#[allow(unused_imports)]
-use crate::utils::print_2d_matrix;
+use crate::input::Input;
+use crate::utils::nth_state;
 
-use std::collections::HashMap;
+use std::fmt;
 
-#[derive(Eq, PartialEq, Hash, Clone)]
-enum Direction {
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+pub enum Direction {
     North,
     South,
     East,
     West,
 }
 
-fn parse_input(input: &str) -> Vec<Vec<char>> {
-    let mut platform = Vec::new();
+// Each row and column is stored as a bitset, so the platform can be at most 128 tiles across either way
+const MAX_SIZE: usize = 128;
 
-    for line in input.split("\n").map(|line| line.trim()) {
-        if line == "" {
-            continue;
-        }
+#[derive(Debug, PartialEq)]
+pub enum PlatformError {
+    // A tile which is not ., O or #, and its position
+    UnknownTile(char, (usize, usize)),
+    TooLarge { rows: usize, columns: usize },
+}
 
-        let mut row = Vec::new();
-        for c in line.chars() {
-            row.push(c);
+impl fmt::Display for PlatformError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlatformError::UnknownTile(tile, (i, j)) => {
+                write!(f, "Unknown tile '{}' at ({}, {})", tile, i, j)
+            }
+            PlatformError::TooLarge { rows, columns } => write!(
+                f,
+                "Platform is {}x{}, larger than {2}x{2}",
+                rows, columns, MAX_SIZE
+            ),
         }
-        platform.push(row);
     }
-
-    platform
 }
 
-fn tilt_platform(platform: &Vec<Vec<char>>, direction: &Direction) -> Vec<Vec<char>> {
-    let n = platform.len();
-    let m = platform[0].len();
-
-    let mut new_state = platform.clone();
-
-    match direction {
-        Direction::North => {
-            for j in 0..m {
-                let mut rounded_rocks = 0;
-                let mut last_anchor = 0;
-                for i in 0..n {
-                    match platform[i][j] {
-                        '.' => {}
-                        'O' => {
-                            rounded_rocks = rounded_rocks + 1;
-                            new_state[i][j] = '.'
-                        }
-                        '#' => {
-                            // Roll rocks
-                            for x in last_anchor..(last_anchor + rounded_rocks) {
-                                new_state[x][j] = 'O'
-                            }
-
-                            // Reset anchor variabes
-                            rounded_rocks = 0;
-                            last_anchor = i + 1;
-                        }
-                        _ => unreachable!(),
-                    }
-                }
+// Platform stored as one bitset per row, with bit j set for a rock in column j.
+// The cube-shaped rocks never move, so the segments of each row and column between them are precomputed.
+#[derive(Clone, PartialEq)]
+struct Platform {
+    n: usize,
+    m: usize,
+    cube_rocks: Vec<u128>,
+    rounded_rocks: Vec<u128>,
+    row_segments: Vec<Vec<u128>>,
+    column_segments: Vec<Vec<u128>>,
+}
 
-                if rounded_rocks != 0 {
-                    for x in last_anchor..(last_anchor + rounded_rocks) {
-                        new_state[x][j] = 'O'
-                    }
-                }
+// Maximal runs of the lowest `length` bits which are not blocked
+fn find_segments(blocked: u128, length: usize) -> Vec<u128> {
+    let mut segments = Vec::new();
+    let mut segment = 0;
+    for k in 0..length {
+        if blocked & (1 << k) != 0 {
+            if segment != 0 {
+                segments.push(segment);
             }
+            segment = 0;
+        } else {
+            segment |= 1 << k;
         }
-        Direction::South => {
-            for j in 0..m {
-                let mut rounded_rocks = 0;
-                let mut last_anchor = n - 1;
-                for i in (0..n).rev() {
-                    match platform[i][j] {
-                        '.' => {}
-                        'O' => {
-                            rounded_rocks = rounded_rocks + 1;
-                            new_state[i][j] = '.'
-                        }
-                        '#' => {
-                            // Roll rocks
-                            for x in (last_anchor + 1 - rounded_rocks)..=last_anchor {
-                                new_state[x][j] = 'O'
-                            }
-
-                            // Reset anchor variabes
-                            rounded_rocks = 0;
-                            if i > 0 {
-                                last_anchor = i - 1;
-                            }
-                        }
-                        _ => unreachable!(),
-                    }
-                }
+    }
+    if segment != 0 {
+        segments.push(segment);
+    }
+    segments
+}
 
-                if rounded_rocks != 0 {
-                    for x in (last_anchor + 1 - rounded_rocks)..=last_anchor {
-                        new_state[x][j] = 'O'
-                    }
-                }
-            }
+// Swap the rows and columns of an n by m bit matrix
+fn transpose(lines: &[u128], m: usize) -> Vec<u128> {
+    let mut ret = vec![0; m];
+    for (i, line) in lines.iter().enumerate() {
+        let mut bits = *line;
+        while bits != 0 {
+            let j = bits.trailing_zeros() as usize;
+            ret[j] |= 1 << i;
+            bits &= bits - 1;
         }
-        Direction::East => {
-            for i in 0..n {
-                let mut rounded_rocks = 0;
-                let mut last_anchor = m - 1;
-                for j in (0..m).rev() {
-                    match platform[i][j] {
-                        '.' => {}
-                        'O' => {
-                            rounded_rocks = rounded_rocks + 1;
-                            new_state[i][j] = '.'
-                        }
-                        '#' => {
-                            // Roll rocks
-                            for y in (last_anchor + 1 - rounded_rocks)..=last_anchor {
-                                new_state[i][y] = 'O'
-                            }
-
-                            // Reset anchor variabes
-                            rounded_rocks = 0;
-                            if j > 0 {
-                                last_anchor = j - 1;
-                            }
-                        }
-                        _ => unreachable!(),
-                    }
-                }
+    }
+    ret
+}
 
-                if rounded_rocks != 0 {
-                    for y in (last_anchor + 1 - rounded_rocks)..=last_anchor {
-                        new_state[i][y] = 'O'
-                    }
-                }
-            }
+// Roll every rock in each segment of the line towards the lowest (or highest) bit of the segment
+fn roll(line: u128, segments: &[u128], towards_low_bit: bool) -> u128 {
+    let mut ret = 0;
+    for segment in segments {
+        let rocks = (line & segment).count_ones();
+        if rocks == 0 {
+            continue;
         }
-        Direction::West => {
-            for i in 0..n {
-                let mut rounded_rocks = 0;
-                let mut last_anchor = 0;
-                for j in 0..m {
-                    match platform[i][j] {
-                        '.' => {}
-                        'O' => {
-                            rounded_rocks = rounded_rocks + 1;
-                            new_state[i][j] = '.'
-                        }
-                        '#' => {
-                            // Roll rocks
-                            for y in last_anchor..(last_anchor + rounded_rocks) {
-                                new_state[i][y] = 'O'
-                            }
-
-                            // Reset anchor variabes
-                            rounded_rocks = 0;
-                            last_anchor = j + 1;
-                        }
-                        _ => unreachable!(),
-                    }
-                }
 
-                if rounded_rocks != 0 {
-                    for y in last_anchor..(last_anchor + rounded_rocks) {
-                        new_state[i][y] = 'O'
-                    }
-                }
-            }
+        if towards_low_bit {
+            let low = segment & segment.wrapping_neg();
+            let high = low << (rocks - 1);
+            ret |= (high - low) | high;
+        } else {
+            let high = 1 << (127 - segment.leading_zeros());
+            let low = high >> (rocks - 1);
+            ret |= (high - low) | high;
         }
     }
-
-    new_state
+    ret
 }
 
-pub fn day_14_calcuate_total_load_on_north_support_beams(input: &str) -> usize {
-    let mut platform = parse_input(input);
-
-    let new_state = tilt_platform(&mut platform, &Direction::North);
+impl Platform {
+    fn parse(input: &str) -> Result<Platform, PlatformError> {
+        let input = Input::new(input);
+        let lines = input
+            .lines()
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>();
+
+        // Checked up front, as the bitsets cannot hold anything larger
+        let n = lines.len();
+        let m = lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        if n > MAX_SIZE || m > MAX_SIZE {
+            return Err(PlatformError::TooLarge {
+                rows: n,
+                columns: m,
+            });
+        }
 
-    let mut acc = 0;
-    let n = new_state.len();
-    for i in 0..n {
-        for c in new_state[i].iter() {
-            if *c == 'O' {
-                acc = acc + (n - i);
+        let mut cube_rocks = Vec::new();
+        let mut rounded_rocks = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            let (mut cube_row, mut rounded_row) = (0, 0);
+            for (j, c) in line.chars().enumerate() {
+                match c {
+                    '.' => {}
+                    'O' => rounded_row |= 1 << j,
+                    '#' => cube_row |= 1 << j,
+                    _ => return Err(PlatformError::UnknownTile(c, (i, j))),
+                }
             }
+            cube_rocks.push(cube_row);
+            rounded_rocks.push(rounded_row);
         }
-    }
 
-    acc
-}
+        let row_segments = cube_rocks
+            .iter()
+            .map(|row| find_segments(*row, m))
+            .collect();
+        let column_segments = transpose(&cube_rocks, m)
+            .iter()
+            .map(|column| find_segments(*column, n))
+            .collect();
+
+        Ok(Platform {
+            n,
+            m,
+            cube_rocks,
+            rounded_rocks,
+            row_segments,
+            column_segments,
+        })
+    }
 
-fn run_spin_cycles(platform: &Vec<Vec<char>>, n: usize) -> Vec<Vec<char>> {
-    let mut current_state = platform.clone();
+    fn to_matrix(&self) -> Vec<Vec<char>> {
+        (0..self.n)
+            .map(|i| {
+                (0..self.m)
+                    .map(|j| {
+                        if self.rounded_rocks[i] & (1 << j) != 0 {
+                            'O'
+                        } else if self.cube_rocks[i] & (1 << j) != 0 {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
 
-    let mut cycle_cache: HashMap<Vec<Vec<char>>, Vec<Vec<char>>> = HashMap::new();
-    let mut tilt_cache: HashMap<(Vec<Vec<char>>, Direction), Vec<Vec<char>>> = HashMap::new();
+    fn tilt(&self, direction: Direction) -> Platform {
+        let rounded_rocks = match direction {
+            Direction::West | Direction::East => self
+                .rounded_rocks
+                .iter()
+                .zip(&self.row_segments)
+                .map(|(row, segments)| roll(*row, segments, direction == Direction::West))
+                .collect(),
+            Direction::North | Direction::South => {
+                let columns = transpose(&self.rounded_rocks, self.m)
+                    .iter()
+                    .zip(&self.column_segments)
+                    .map(|(column, segments)| {
+                        roll(*column, segments, direction == Direction::North)
+                    })
+                    .collect::<Vec<_>>();
+                transpose(&columns, self.n)
+            }
+        };
 
-    let (mut outer_cache_hits, mut outer_cache_misses) = (0, 0);
-    let (mut inner_cache_hits, mut inner_cache_misses) = (0, 0);
+        Platform {
+            rounded_rocks,
+            ..self.clone()
+        }
+    }
 
-    // Manually execute the cycles until the outer cache registers a hit, indicating that we have visited a state before
-    // Because we are applying the same operation to the state, this will eventually lead us to state where we first got a hit on the other cache, i.e. a cycle
-    // Remember this first state to find the size of the large cycle
-    let mut manual_cycles = 0;
-    let mut large_cycle_end = None;
-    for i in 0..n {
-        let mut intermediate_state = current_state.clone();
-        if let Some(new_state) = cycle_cache.get(&current_state.clone()) {
-            outer_cache_hits = outer_cache_hits + 1;
+    fn spin_cycle(&self) -> Platform {
+        self.tilt(Direction::North)
+            .tilt(Direction::West)
+            .tilt(Direction::South)
+            .tilt(Direction::East)
+    }
 
-            large_cycle_end = Some(current_state.clone());
+    fn load_on_north_support_beams(&self) -> usize {
+        self.load_on_support_beams(Direction::North)
+    }
 
-            current_state = new_state.to_vec();
+    // Each rounded rock contributes its distance from the opposite wall, counting its own tile
+    fn load_on_support_beams(&self, wall: Direction) -> usize {
+        let (lines, length) = match wall {
+            Direction::North | Direction::South => (self.rounded_rocks.clone(), self.n),
+            Direction::West | Direction::East => (transpose(&self.rounded_rocks, self.m), self.m),
+        };
+
+        lines
+            .iter()
+            .enumerate()
+            .map(|(k, line)| {
+                let distance = match wall {
+                    Direction::North | Direction::West => length - k,
+                    Direction::South | Direction::East => k + 1,
+                };
+                distance * line.count_ones() as usize
+            })
+            .sum()
+    }
 
-            manual_cycles = i + 1;
-            break;
-        } else {
-            outer_cache_misses = outer_cache_misses + 1;
-            for direction in [
-                Direction::North,
-                Direction::West,
-                Direction::South,
-                Direction::East,
-            ] {
-                intermediate_state = if let Some(new_state) =
-                    tilt_cache.get(&(intermediate_state.clone(), direction.clone()))
-                {
-                    inner_cache_hits = inner_cache_hits + 1;
-                    new_state.to_vec()
-                } else {
-                    inner_cache_misses = inner_cache_misses + 1;
-                    let new_state = tilt_platform(&intermediate_state, &direction);
-                    tilt_cache.insert((intermediate_state, direction), new_state.clone());
-                    new_state
-                }
-            }
-            cycle_cache.insert(current_state, intermediate_state.clone());
-            current_state = intermediate_state
+    fn run_program(&self, program: &[(Vec<Direction>, usize)]) -> Platform {
+        let mut platform = self.clone();
+        for (tilts, repetitions) in program {
+            platform = nth_state(
+                &platform,
+                |platform| {
+                    tilts.iter().fold(platform.clone(), |platform, direction| {
+                        platform.tilt(*direction)
+                    })
+                },
+                *repetitions,
+            );
         }
+        platform
     }
+}
 
-    // If we have not detected a cycle, it also means that we have completed the required number of cycles and can skip this step
-    if let Some(large_cycle_end) = large_cycle_end {
-        println!("Detected large cycle");
-
-        // Determine the size of the large cycle by iterating until we find the first state
-        let mut large_cycle_length = 0;
-        for i in manual_cycles..n {
-            if let Some(new_state) = cycle_cache.get(&current_state.clone()) {
-                outer_cache_hits = outer_cache_hits + 1;
-
-                current_state = new_state.to_vec();
+#[derive(Debug, PartialEq)]
+pub enum TiltProgramError {
+    UnknownInstruction(String),
+    // A repetition which is not a number, or has no tilts before it to repeat
+    InvalidRepetition(String),
+    InvalidPlatform(PlatformError),
+}
 
-                if current_state == large_cycle_end {
-                    large_cycle_length = i - manual_cycles;
-                    manual_cycles = i + 1;
-                    break;
-                }
-            } else {
-                unreachable!();
+impl fmt::Display for TiltProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TiltProgramError::UnknownInstruction(instruction) => {
+                write!(f, "Unknown instruction {}", instruction)
+            }
+            TiltProgramError::InvalidRepetition(instruction) => {
+                write!(f, "Invalid repetition {}", instruction)
             }
+            TiltProgramError::InvalidPlatform(error) => write!(f, "{}", error),
         }
+    }
+}
 
-        println!("Determined large cycle length: {}", large_cycle_length);
-
-        let cycles_remaining = n - manual_cycles;
+// A program is a whitespace separated list of tilts, N, S, E or W.
+// xK repeats the tilts since the previous repetition K times, e.g. `N W S E x1000000000`.
+fn parse_tilt_program(program: &str) -> Result<Vec<(Vec<Direction>, usize)>, TiltProgramError> {
+    let mut ret = Vec::new();
+    let mut tilts = Vec::new();
+
+    for instruction in program.split_whitespace() {
+        let direction = match instruction {
+            "N" => Direction::North,
+            "S" => Direction::South,
+            "E" => Direction::East,
+            "W" => Direction::West,
+            _ => {
+                if let Some(repetitions) = instruction.strip_prefix('x') {
+                    match repetitions.parse::<usize>() {
+                        Ok(repetitions) if !tilts.is_empty() => {
+                            ret.push((tilts, repetitions));
+                            tilts = Vec::new();
+                            continue;
+                        }
+                        _ => {
+                            return Err(TiltProgramError::InvalidRepetition(
+                                instruction.to_string(),
+                            ))
+                        }
+                    }
+                }
+                return Err(TiltProgramError::UnknownInstruction(
+                    instruction.to_string(),
+                ));
+            }
+        };
+        tilts.push(direction);
+    }
+    if !tilts.is_empty() {
+        ret.push((tilts, 1));
+    }
 
-        println!(
-            "Current cycle: {}, Remaining cycles: {}",
-            manual_cycles, cycles_remaining
-        );
+    Ok(ret)
+}
 
-        let skipped_cycles = (cycles_remaining / large_cycle_length) * large_cycle_length;
-        let next_manual_cycle = manual_cycles + skipped_cycles;
+pub fn day_14_calcuate_total_load_on_north_support_beams(input: &str) -> usize {
+    let platform = Platform::parse(input).unwrap();
 
-        println!(
-            "Skipping ahead {} cycles to cycle {}",
-            skipped_cycles, next_manual_cycle
-        );
+    platform
+        .tilt(Direction::North)
+        .load_on_north_support_beams()
+}
 
-        for _ in next_manual_cycle..n {
-            if let Some(new_state) = cycle_cache.get(&current_state.clone()) {
-                outer_cache_hits = outer_cache_hits + 1;
+fn run_spin_cycles(platform: &Platform, n: usize) -> Platform {
+    nth_state(platform, |platform| platform.spin_cycle(), n)
+}
 
-                current_state = new_state.to_vec();
-            } else {
-                unreachable!();
-            }
-        }
-    }
+pub fn day_14_calcuate_total_load_on_north_support_beams_part_2(input: &str) -> usize {
+    let platform = Platform::parse(input).unwrap();
 
-    println!(
-        "{} inner cache hits out of {} attempts",
-        inner_cache_hits,
-        inner_cache_hits + inner_cache_misses
-    );
-    println!(
-        "{} outer cache hits out of {} attempts",
-        outer_cache_hits,
-        outer_cache_hits + outer_cache_misses
-    );
-
-    current_state
+    run_spin_cycles(&platform, 1000000000).load_on_north_support_beams()
 }
 
-pub fn day_14_calcuate_total_load_on_north_support_beams_part_2(input: &str) -> usize {
-    let platform = parse_input(input);
+pub fn day_14_run_tilt_program(input: &str, program: &str) -> Result<String, TiltProgramError> {
+    let program = parse_tilt_program(program)?;
 
-    let new_state = run_spin_cycles(&platform, 1000000000);
+    let platform = Platform::parse(input)
+        .map_err(TiltProgramError::InvalidPlatform)?
+        .run_program(&program);
 
-    let mut acc = 0;
-    let n = new_state.len();
-    for i in 0..n {
-        for c in new_state[i].iter() {
-            if *c == 'O' {
-                acc = acc + (n - i);
-            }
-        }
-    }
+    Ok(platform
+        .to_matrix()
+        .iter()
+        .map(|row| row.iter().collect::<String>() + "\n")
+        .collect())
+}
 
-    acc
+pub fn day_14_calculate_total_load_after_tilt_program(
+    input: &str,
+    program: &str,
+    wall: Direction,
+) -> Result<usize, TiltProgramError> {
+    let program = parse_tilt_program(program)?;
+
+    Ok(Platform::parse(input)
+        .map_err(TiltProgramError::InvalidPlatform)?
+        .run_program(&program)
+        .load_on_support_beams(wall))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::utils::parse_2d_matrix;
+
     const EXAMPLE: &str = r#"O....#....
     O.OO#....#
     .....##...
@@ -347,38 +387,161 @@ mod tests {
     }
 
     #[test]
-    fn test_tilt() {
-        let platform = parse_input(EXAMPLE);
-        print_2d_matrix(&platform);
-
-        let north_tilt = tilt_platform(&platform, &Direction::North);
-        print_2d_matrix(&north_tilt);
-
-        let south_tilt = tilt_platform(&platform, &Direction::South);
-        print_2d_matrix(&south_tilt);
+    fn test_platform_parse_errors() {
+        assert_eq!(
+            Some(PlatformError::UnknownTile('x', (1, 2))),
+            Platform::parse("O..\n#.x").err()
+        );
+        assert_eq!(
+            Some(PlatformError::TooLarge {
+                rows: 2,
+                columns: 129
+            }),
+            Platform::parse(&format!("O\n{}", ".".repeat(129))).err()
+        );
+        assert_eq!(
+            Some(PlatformError::TooLarge {
+                rows: 129,
+                columns: 1
+            }),
+            Platform::parse(&".\n".repeat(129)).err()
+        );
+        assert!(Platform::parse(&format!("{}\n", ".".repeat(128)).repeat(128)).is_ok());
+        assert_eq!(
+            Err(TiltProgramError::InvalidPlatform(
+                PlatformError::UnknownTile('?', (0, 0))
+            )),
+            day_14_run_tilt_program("?", "N")
+        );
+    }
 
-        let east_tilt = tilt_platform(&platform, &Direction::East);
-        print_2d_matrix(&east_tilt);
+    #[test]
+    fn test_tilt() {
+        let platform = Platform::parse(EXAMPLE).unwrap();
+        assert_eq!(parse_2d_matrix(EXAMPLE), platform.to_matrix());
 
-        let west_tilt = tilt_platform(&platform, &Direction::West);
-        print_2d_matrix(&west_tilt);
+        assert_eq!(
+            parse_2d_matrix(
+                r#"OOOO.#.O..
+                OO..#....#
+                OO..O##..O
+                O..#.OO...
+                ........#.
+                ..#....#.#
+                ..O..#.O.O
+                ..O.......
+                #....###..
+                #....#...."#
+            ),
+            platform.tilt(Direction::North).to_matrix()
+        );
+        assert_eq!(
+            parse_2d_matrix(
+                r#".....#....
+                ....#....#
+                ...O.##...
+                ...#......
+                O.O....O#O
+                O.#..O.#.#
+                O....#....
+                OO....OO..
+                #OO..###..
+                #OO.O#...O"#
+            ),
+            platform.tilt(Direction::South).to_matrix()
+        );
+        assert_eq!(
+            parse_2d_matrix(
+                r#"....O#....
+                .OOO#....#
+                .....##...
+                .OO#....OO
+                ......OO#.
+                .O#...O#.#
+                ....O#..OO
+                .........O
+                #....###..
+                #..OO#...."#
+            ),
+            platform.tilt(Direction::East).to_matrix()
+        );
+        assert_eq!(
+            parse_2d_matrix(
+                r#"O....#....
+                OOO.#....#
+                .....##...
+                OO.#OO....
+                OO......#.
+                O.#O...#.#
+                O....#OO..
+                O.........
+                #....###..
+                #OO..#...."#
+            ),
+            platform.tilt(Direction::West).to_matrix()
+        );
     }
 
     #[test]
     fn test_spin_cycle() {
-        let mut platform = parse_input(EXAMPLE);
+        let mut platform = Platform::parse(EXAMPLE).unwrap();
 
         platform = run_spin_cycles(&platform, 1);
-        print_2d_matrix(&platform);
+        assert_eq!(
+            parse_2d_matrix(
+                r#".....#....
+                ....#...O#
+                ...OO##...
+                .OO#......
+                .....OOO#.
+                .O#...O#.#
+                ....O#....
+                ......OOOO
+                #...O###..
+                #..OO#...."#
+            ),
+            platform.to_matrix()
+        );
 
         platform = run_spin_cycles(&platform, 1);
-        print_2d_matrix(&platform);
+        assert_eq!(
+            parse_2d_matrix(
+                r#".....#....
+                ....#...O#
+                .....##...
+                ..O#......
+                .....OOO#.
+                .O#...O#.#
+                ....O#...O
+                .......OOO
+                #..OO###..
+                #.OOO#...O"#
+            ),
+            platform.to_matrix()
+        );
 
         platform = run_spin_cycles(&platform, 1);
-        print_2d_matrix(&platform);
+        assert_eq!(
+            parse_2d_matrix(
+                r#".....#....
+                ....#...O#
+                .....##...
+                ..O#......
+                .....OOO#.
+                .O#...O#.#
+                ....O#...O
+                .......OOO
+                #...O###.O
+                #.OOO#...O"#
+            ),
+            platform.to_matrix()
+        );
 
-        platform = run_spin_cycles(&platform, 1000000);
-        print_2d_matrix(&platform);
+        // The example settles into a cycle of length 7
+        assert_eq!(
+            run_spin_cycles(&platform, 1000000).to_matrix(),
+            run_spin_cycles(&platform, 1000000 - 7).to_matrix()
+        );
     }
 
     #[test]
@@ -388,4 +551,110 @@ mod tests {
             day_14_calcuate_total_load_on_north_support_beams_part_2(EXAMPLE)
         );
     }
+
+    #[test]
+    fn test_parse_tilt_program() {
+        assert_eq!(
+            Ok(vec![
+                (vec![Direction::North], 1),
+                (
+                    vec![
+                        Direction::North,
+                        Direction::West,
+                        Direction::South,
+                        Direction::East
+                    ],
+                    1000000000
+                ),
+                (vec![Direction::East, Direction::North], 1)
+            ]),
+            parse_tilt_program("N x1 N W S E x1000000000 E N")
+        );
+        assert_eq!(
+            Err(TiltProgramError::UnknownInstruction("Q".to_string())),
+            parse_tilt_program("N Q")
+        );
+        assert_eq!(
+            Err(TiltProgramError::InvalidRepetition("x3".to_string())),
+            parse_tilt_program("x3 N")
+        );
+        assert_eq!(
+            Err(TiltProgramError::InvalidRepetition("xN".to_string())),
+            parse_tilt_program("N xN")
+        );
+    }
+
+    #[test]
+    fn test_day_14_run_tilt_program() {
+        assert_eq!(
+            Ok(Platform::parse(EXAMPLE)
+                .unwrap()
+                .tilt(Direction::North)
+                .tilt(Direction::East)
+                .tilt(Direction::North)
+                .to_matrix()),
+            day_14_run_tilt_program(EXAMPLE, "N E N")
+                .map(|platform| parse_2d_matrix(platform.trim()))
+        );
+        assert_eq!(
+            Ok(run_spin_cycles(&Platform::parse(EXAMPLE).unwrap(), 3).to_matrix()),
+            day_14_run_tilt_program(EXAMPLE, "N W S E x3")
+                .map(|platform| parse_2d_matrix(platform.trim()))
+        );
+    }
+
+    #[test]
+    fn test_day_14_calculate_total_load_after_tilt_program() {
+        assert_eq!(
+            Ok(136),
+            day_14_calculate_total_load_after_tilt_program(EXAMPLE, "N", Direction::North)
+        );
+        assert_eq!(
+            Ok(64),
+            day_14_calculate_total_load_after_tilt_program(
+                EXAMPLE,
+                "N W S E x1000000000",
+                Direction::North
+            )
+        );
+        // Tilting towards a wall puts the most load on it
+        let platform = Platform::parse(EXAMPLE).unwrap();
+        for wall in [
+            Direction::North,
+            Direction::South,
+            Direction::East,
+            Direction::West,
+        ] {
+            assert_eq!(
+                platform.tilt(wall).load_on_support_beams(wall),
+                [
+                    Direction::North,
+                    Direction::South,
+                    Direction::East,
+                    Direction::West
+                ]
+                .iter()
+                .map(|direction| platform.tilt(*direction).load_on_support_beams(wall))
+                .max()
+                .unwrap()
+            );
+        }
+        // A lone rock in the top left corner of a 2x3 platform
+        assert_eq!(
+            Ok(2),
+            day_14_calculate_total_load_after_tilt_program("O..\n...", "N", Direction::North)
+        );
+        assert_eq!(
+            Ok(1),
+            day_14_calculate_total_load_after_tilt_program("O..\n...", "N", Direction::South)
+        );
+        assert_eq!(
+            Ok(3),
+            day_14_calculate_total_load_after_tilt_program("O..\n...", "N", Direction::West)
+        );
+        assert_eq!(
+            Ok(3),
+            day_14_calculate_total_load_after_tilt_program("O..\n...", "E", Direction::East)
+        );
+    }
 }
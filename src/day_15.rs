@@ -1,152 +1,726 @@
+use std::collections::HashMap;
 use std::convert::TryInto;
+use std::fmt;
+
+use crate::input::Input;
+use crate::streaming::LineSolver;
 
 enum Operation {
     Remove(String),
     Set((String, u8)),
 }
 
-fn hash_string(s: &str) -> u8 {
-    #[allow(non_snake_case)]
-    let ASCII_range = '\u{0}'..='\u{126}';
+// Most entries in the table of which hashes can reach a target, one byte each
+const MAX_REACHABLE_TABLE: usize = 1 << 24;
+
+#[derive(Debug, PartialEq)]
+pub enum HashFunctionError {
+    ZeroModulus,
+    // The search for colliding labels would need a table of (max_length + 1) * modulus entries
+    SearchTooLarge { modulus: usize, max_length: usize },
+}
+
+impl fmt::Display for HashFunctionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashFunctionError::ZeroModulus => write!(f, "The modulus must not be 0"),
+            HashFunctionError::SearchTooLarge {
+                modulus,
+                max_length,
+            } => write!(
+                f,
+                "Too many hashes to search for labels up to {} characters long modulo {}",
+                max_length, modulus
+            ),
+        }
+    }
+}
+
+// The HASH algorithm with its constants as parameters: for each ASCII code c in turn, hash = (hash + c) * multiplier
+// mod modulus
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HashFunction {
+    multiplier: usize,
+    modulus: usize,
+}
+
+impl HashFunction {
+    pub fn new(multiplier: usize, modulus: usize) -> Result<HashFunction, HashFunctionError> {
+        if modulus == 0 {
+            return Err(HashFunctionError::ZeroModulus);
+        }
+
+        Ok(HashFunction {
+            multiplier,
+            modulus,
+        })
+    }
+
+    // The HASH from the puzzle, which picks one of 256 boxes
+    pub fn standard() -> HashFunction {
+        HashFunction {
+            multiplier: 17,
+            modulus: 256,
+        }
+    }
 
-    let mut hash: u8 = 0;
+    // c must be an ASCII character
+    fn update(&self, c: char, hash: usize) -> usize {
+        let modulus = self.modulus as u128;
+
+        // Reducing before multiplying keeps every intermediate value within a u128
+        (((hash as u128 + c as u128) % modulus) * (self.multiplier as u128 % modulus) % modulus)
+            as usize
+    }
 
-    for c in s.chars() {
-        if !ASCII_range.contains(&c) {
-            panic!("Non ASCII character in string");
+    // None if the string is not ASCII
+    pub fn hash(&self, s: &str) -> Option<usize> {
+        if !s.is_ascii() {
+            return None;
         }
 
-        hash = update_hash(c, hash);
+        Some(s.chars().fold(0, |hash, c| self.update(c, hash)))
     }
 
-    hash
+    // Every label made from the ASCII characters of the alphabet, up to max_length characters long, which hashes to
+    // target. Shorter labels come first, then labels in the order of the alphabet.
+    pub fn colliding_labels(
+        &self,
+        target: usize,
+        alphabet: &str,
+        max_length: usize,
+    ) -> Result<CollidingLabels, HashFunctionError> {
+        if max_length
+            .checked_add(1)
+            .and_then(|lengths| lengths.checked_mul(self.modulus))
+            .is_none_or(|entries| entries > MAX_REACHABLE_TABLE)
+        {
+            return Err(HashFunctionError::SearchTooLarge {
+                modulus: self.modulus,
+                max_length,
+            });
+        }
+
+        let alphabet = alphabet
+            .chars()
+            .filter(|c| c.is_ascii())
+            .collect::<Vec<_>>();
+
+        // reachable[k][hash] is whether k more characters can take hash to target
+        let mut reachable = vec![(0..self.modulus)
+            .map(|hash| hash == target)
+            .collect::<Vec<_>>()];
+        for k in 1..=max_length {
+            let next = (0..self.modulus)
+                .map(|hash| {
+                    alphabet
+                        .iter()
+                        .any(|c| reachable[k - 1][self.update(*c, hash)])
+                })
+                .collect();
+            reachable.push(next);
+        }
+
+        Ok(CollidingLabels {
+            hash_function: *self,
+            alphabet,
+            reachable,
+            length: 1,
+            fresh: true,
+            indices: Vec::new(),
+            hashes: vec![0],
+        })
+    }
 }
 
-// c must be an ASCII character
-fn update_hash(c: char, hash: u8) -> u8 {
-    let mut acc: usize = hash as usize;
+// Labels found by HashFunction::colliding_labels. The search never explores a prefix which cannot be completed into a
+// label, so each label takes at most max_length * alphabet size steps to find.
+pub struct CollidingLabels {
+    hash_function: HashFunction,
+    alphabet: Vec<char>,
+    reachable: Vec<Vec<bool>>,
+    // Length of the labels currently being searched, and whether the search at this length has only just started
+    length: usize,
+    fresh: bool,
+    // Index into the alphabet of each character of the label so far, and the hash after each of its prefixes
+    indices: Vec<usize>,
+    hashes: Vec<usize>,
+}
 
-    // Determine the ASCII code for the current character of the string.
-    // Increase the current value by the ASCII code you just determined.
-    acc = acc + (c as usize);
+impl CollidingLabels {
+    // The next label of the current length, if any
+    fn next_label(&mut self) -> Option<String> {
+        let mut next_index = if self.fresh {
+            0
+        } else {
+            self.hashes.pop();
+            self.indices.pop()? + 1
+        };
+        self.fresh = false;
+
+        loop {
+            if next_index == self.alphabet.len() {
+                self.hashes.pop();
+                next_index = self.indices.pop()? + 1;
+                continue;
+            }
 
-    // Set the current value to itself multiplied by 17.
-    acc = acc * 17;
-    // Set the current value to the remainder of dividing itself by 256.
-    acc = acc % 256;
+            let depth = self.indices.len();
+            let hash = self
+                .hash_function
+                .update(self.alphabet[next_index], self.hashes[depth]);
+            if !self.reachable[self.length - depth - 1][hash] {
+                next_index += 1;
+                continue;
+            }
 
-    acc.try_into().unwrap()
+            self.indices.push(next_index);
+            self.hashes.push(hash);
+            if self.indices.len() == self.length {
+                return Some(self.indices.iter().map(|&i| self.alphabet[i]).collect());
+            }
+            next_index = 0;
+        }
+    }
+}
+
+impl Iterator for CollidingLabels {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        while self.length < self.reachable.len() {
+            if self.reachable[self.length][0] {
+                if let Some(label) = self.next_label() {
+                    return Some(label);
+                }
+            }
+
+            self.length += 1;
+            self.fresh = true;
+            self.indices.clear();
+            self.hashes = vec![0];
+        }
+
+        None
+    }
+}
+
+fn hash_string(s: &str) -> u8 {
+    HashFunction::standard()
+        .hash(s)
+        .expect("Non ASCII character in string") as u8
+}
+
+// A sequence which puts the given number of lenses into one box and then takes them out again from the front, so
+// that every removal moves every other lens in the box
+pub fn day_15_adversarial_sequence(box_number: u8, lenses: usize) -> String {
+    let labels = HashFunction::standard()
+        .colliding_labels(box_number as usize, "abcdefghijklmnopqrstuvwxyz", 8)
+        .unwrap()
+        .take(lenses)
+        .collect::<Vec<_>>();
+
+    let inserts = labels
+        .iter()
+        .enumerate()
+        .map(|(i, label)| format!("{}={}", label, i % 9 + 1));
+    let removals = labels.iter().map(|label| format!("{}-", label));
+
+    inserts.chain(removals).collect::<Vec<_>>().join(",")
 }
 
 pub fn day_15_determine_verfication_number(initialization_sequence: &str) -> usize {
     let mut acc: usize = 0;
 
-    for step in initialization_sequence.split(",").map(|step| step.trim()) {
-        // Ignore newline characters when parsing the initialization sequence.
-        if step == "\n" {
-            continue;
-        }
+    let initialization_sequence = Input::new(initialization_sequence);
+    for step in initialization_sequence
+        .as_str()
+        .split(',')
+        .map(|step| step.trim())
+    {
         acc = acc + (hash_string(step) as usize);
     }
 
     acc
 }
 
-fn parse_step(step: &str) -> (u8, Operation) {
-    let mut hash: u8 = 0;
+// None unless the step is a label followed by - or by = and a focal length
+fn parse_step(step: &str) -> Option<Operation> {
     let mut label: String = String::from("");
 
     let alpha_lower_range = 'a'..='z';
 
     let mut step_iterator = step.chars();
 
-    let mut c: char = step_iterator.next().unwrap();
+    let mut c: char = step_iterator.next()?;
 
     loop {
         if alpha_lower_range.contains(&c) {
             label.push(c);
-
-            hash = update_hash(c, hash);
         } else {
             break;
         }
-        c = step_iterator.next().unwrap();
+        c = step_iterator.next()?;
     }
 
     match c {
-        '-' => {
-            return (hash, Operation::Remove(label));
-        }
+        '-' => Some(Operation::Remove(label)),
         '=' => {
-            let focal_length: u8 = step_iterator
-                .next()
-                .unwrap()
-                .to_digit(10)
-                .unwrap()
-                .try_into()
-                .unwrap();
+            let focal_length: u8 = step_iterator.next()?.to_digit(10)?.try_into().unwrap();
 
-            return (hash, Operation::Set((label, focal_length)));
+            Some(Operation::Set((label, focal_length)))
         }
-        _ => unreachable!(),
+        _ => None,
     }
 }
 
-pub fn day_15_determine_resultant_focusing_power(initialization_sequence: &str) -> usize {
-    let mut boxes = std::iter::repeat::<Vec<(String, u8)>>(vec![])
-        .take(256)
-        .collect::<Vec<_>>();
+#[derive(Debug, PartialEq)]
+pub struct NonAsciiLabelError {
+    pub label: String,
+}
+
+impl fmt::Display for NonAsciiLabelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Label {} is not ASCII", self.label)
+    }
+}
 
-    for step in initialization_sequence.split(",").map(|step| step.trim()) {
-        // Ignore newline characters when parsing the initialization sequence.
-        if step == "\n" {
-            continue;
+// The box a label goes in
+fn box_number(label: &str) -> Result<usize, NonAsciiLabelError> {
+    HashFunction::standard()
+        .hash(label)
+        .ok_or_else(|| NonAsciiLabelError {
+            label: label.to_string(),
+        })
+}
+
+struct Slot<V> {
+    label: String,
+    value: V,
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
+// The HASHMAP: 256 boxes, chosen by the HASH of each label, each holding its labels in the order they were first
+// inserted. Every box is a doubly linked list threaded through one arena of slots, with an index from label to slot,
+// so inserting, updating and removing a label take constant time.
+pub struct LensMap<V> {
+    slots: Vec<Option<Slot<V>>>,
+    vacant: Vec<usize>,
+    index: HashMap<String, usize>,
+    // First and last slot of each box, if it is not empty
+    ends: Vec<Option<(usize, usize)>>,
+}
+
+impl<V> LensMap<V> {
+    pub fn new() -> LensMap<V> {
+        LensMap {
+            slots: Vec::new(),
+            vacant: Vec::new(),
+            index: HashMap::new(),
+            ends: vec![None; 256],
+        }
+    }
+
+    fn slot(&self, i: usize) -> &Slot<V> {
+        self.slots[i].as_ref().unwrap()
+    }
+
+    fn slot_mut(&mut self, i: usize) -> &mut Slot<V> {
+        self.slots[i].as_mut().unwrap()
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    pub fn get(&self, label: &str) -> Option<&V> {
+        self.index.get(label).map(|&i| &self.slot(i).value)
+    }
+
+    // Replaces the value of a label already in its box without moving it, otherwise adds the label to the back of its
+    // box. Returns the value replaced, or an error if the label is not ASCII and so has no box.
+    pub fn insert(&mut self, label: &str, value: V) -> Result<Option<V>, NonAsciiLabelError> {
+        let b = box_number(label)?;
+        if let Some(&i) = self.index.get(label) {
+            return Ok(Some(std::mem::replace(&mut self.slot_mut(i).value, value)));
         }
 
-        let (hash, operation) = parse_step(step);
+        let slot = Slot {
+            label: label.to_string(),
+            value,
+            prev: self.ends[b].map(|(_, last)| last),
+            next: None,
+        };
+        let i = match self.vacant.pop() {
+            Some(i) => {
+                self.slots[i] = Some(slot);
+                i
+            }
+            None => {
+                self.slots.push(Some(slot));
+                self.slots.len() - 1
+            }
+        };
 
-        let r#box = &mut boxes[hash as usize];
-        let n = r#box.len();
+        self.ends[b] = match self.ends[b] {
+            Some((first, last)) => {
+                self.slot_mut(last).next = Some(i);
+                Some((first, i))
+            }
+            None => Some((i, i)),
+        };
+        self.index.insert(label.to_string(), i);
 
-        match operation {
+        Ok(None)
+    }
+
+    // Takes the label out of its box, moving the labels behind it forward. Returns its value, or an error if the label
+    // is not ASCII.
+    pub fn remove(&mut self, label: &str) -> Result<Option<V>, NonAsciiLabelError> {
+        let b = box_number(label)?;
+        let i = match self.index.remove(label) {
+            Some(i) => i,
+            None => return Ok(None),
+        };
+        let slot = self.slots[i].take().unwrap();
+        self.vacant.push(i);
+
+        if let Some(prev) = slot.prev {
+            self.slot_mut(prev).next = slot.next;
+        }
+        if let Some(next) = slot.next {
+            self.slot_mut(next).prev = slot.prev;
+        }
+
+        self.ends[b] = match (slot.prev, slot.next) {
+            (None, None) => None,
+            (prev, next) => {
+                let (first, last) = self.ends[b].unwrap();
+                Some((
+                    if first == i { next.unwrap() } else { first },
+                    if last == i { prev.unwrap() } else { last },
+                ))
+            }
+        };
+
+        Ok(Some(slot.value))
+    }
+
+    // The labels in a box from front to back, with their values
+    pub fn lenses(&self, box_number: u8) -> impl Iterator<Item = (&str, &V)> {
+        std::iter::successors(
+            self.ends[box_number as usize].map(|(first, _)| first),
+            move |&i| self.slot(i).next,
+        )
+        .map(move |i| {
+            let slot = self.slot(i);
+            (slot.label.as_str(), &slot.value)
+        })
+    }
+}
+
+impl<V> Default for LensMap<V> {
+    fn default() -> LensMap<V> {
+        LensMap::new()
+    }
+}
+
+impl LensMap<u8> {
+    pub fn box_power(&self, box_number: u8) -> usize {
+        self.lenses(box_number)
+            .enumerate()
+            .map(|(j, (_, focal_length))| {
+                (box_number as usize + 1) * (j + 1) * (*focal_length as usize)
+            })
+            .sum()
+    }
+
+    pub fn focusing_power(&self) -> usize {
+        (0..=255).map(|box_number| self.box_power(box_number)).sum()
+    }
+}
+
+pub fn day_15_determine_resultant_focusing_power(initialization_sequence: &str) -> usize {
+    let mut lenses = LensMap::new();
+
+    let initialization_sequence = Input::new(initialization_sequence);
+    for step in initialization_sequence
+        .as_str()
+        .split(',')
+        .map(|step| step.trim())
+    {
+        // Labels are only ever lowercase letters
+        match parse_step(step).unwrap() {
             Operation::Remove(label) => {
-                for i in 0..n {
-                    if r#box[i].0 == label {
-                        r#box.remove(i);
-                        break;
-                    }
-                }
+                lenses.remove(&label).unwrap();
             }
             Operation::Set((label, focal_length)) => {
-                let mut present = false;
-                for i in 0..n {
-                    if r#box[i].0 == label {
-                        r#box[i] = (label.clone(), focal_length);
-                        present = true;
-                    }
+                lenses.insert(&label, focal_length).unwrap();
+            }
+        }
+    }
+
+    lenses.focusing_power()
+}
+
+#[derive(Debug, PartialEq)]
+pub struct InvalidStepError {
+    pub step: String,
+}
+
+impl fmt::Display for InvalidStepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid step {}", self.step)
+    }
+}
+
+// Running verification number of an initialization sequence read a step at a time
+#[derive(Debug, Default)]
+pub struct VerificationNumber {
+    total: usize,
+}
+
+impl LineSolver for VerificationNumber {
+    type Total = usize;
+    type Error = InvalidStepError;
+
+    const SEPARATOR: u8 = b',';
+
+    fn feed(&mut self, step: &str) -> Result<(), InvalidStepError> {
+        if !step.is_ascii() {
+            return Err(InvalidStepError {
+                step: step.to_string(),
+            });
+        }
+        self.total += hash_string(step) as usize;
+        Ok(())
+    }
+
+    fn total(&self) -> usize {
+        self.total
+    }
+}
+
+// Fenwick tree of prefix sums over values which are only ever added at the end, with value k at tree[k - 1]
+#[derive(Default)]
+struct GrowingFenwickTree {
+    tree: Vec<usize>,
+}
+
+impl GrowingFenwickTree {
+    fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    // Sum of the first k values
+    fn prefix(&self, mut k: usize) -> usize {
+        let mut sum = 0;
+        while k > 0 {
+            sum += self.tree[k - 1];
+            k -= k & k.wrapping_neg();
+        }
+        sum
+    }
+
+    fn push(&mut self, value: usize) {
+        // The new node covers the values since the last one its lowest bit reaches back to
+        let k = self.len() + 1;
+        let covered = self.prefix(k - 1) - self.prefix(k - (k & k.wrapping_neg()));
+        self.tree.push(value + covered);
+    }
+
+    // Changes value k from old to new
+    fn update(&mut self, mut k: usize, old: usize, new: usize) {
+        while k <= self.len() {
+            self.tree[k - 1] = self.tree[k - 1] - old + new;
+            k += k & k.wrapping_neg();
+        }
+    }
+}
+
+// The lenses ever put in one box, in the order they were added, so a lens's slot and the focal lengths behind it take
+// logarithmic time to find. Lenses are numbered from 1 in that order, and lenses taken out are kept with no weight.
+#[derive(Default)]
+struct BoxSlots {
+    // 1 for each lens still in the box
+    occupied: GrowingFenwickTree,
+    focal_lengths: GrowingFenwickTree,
+    // Sum of slot * focal length over the lenses in the box
+    weighted: usize,
+}
+
+impl BoxSlots {
+    // Adds a lens to the back of the box, returning its number
+    fn push(&mut self, focal_length: usize) -> usize {
+        let slot = self.occupied.prefix(self.occupied.len()) + 1;
+        self.occupied.push(1);
+        self.focal_lengths.push(focal_length);
+        self.weighted += slot * focal_length;
+        self.occupied.len()
+    }
+
+    fn replace(&mut self, lens: usize, old: usize, new: usize) {
+        let slot = self.occupied.prefix(lens);
+        self.focal_lengths.update(lens, old, new);
+        self.weighted = self.weighted - slot * old + slot * new;
+    }
+
+    // Takes the lens out, moving every lens behind it forward one slot
+    fn remove(&mut self, lens: usize, focal_length: usize) {
+        let slot = self.occupied.prefix(lens);
+        let behind =
+            self.focal_lengths.prefix(self.focal_lengths.len()) - self.focal_lengths.prefix(lens);
+        self.occupied.update(lens, 1, 0);
+        self.focal_lengths.update(lens, focal_length, 0);
+        self.weighted -= slot * focal_length + behind;
+    }
+}
+
+// Focusing power after each step of an initialization sequence read a step at a time
+pub struct FocusingPower {
+    lenses: LensMap<u8>,
+    // The number of each label's lens in its box, and the slots of every box, so that a step only updates the power of
+    // the box it changed rather than walking it
+    numbers: HashMap<String, usize>,
+    slots: Vec<BoxSlots>,
+    box_powers: [usize; 256],
+    power: usize,
+}
+
+impl Default for FocusingPower {
+    fn default() -> FocusingPower {
+        FocusingPower {
+            lenses: LensMap::new(),
+            numbers: HashMap::new(),
+            slots: (0..256).map(|_| BoxSlots::default()).collect(),
+            box_powers: [0; 256],
+            power: 0,
+        }
+    }
+}
+
+impl FocusingPower {
+    // Carries out the step, returning the box it changed
+    fn apply(&mut self, step: &str) -> Result<u8, InvalidStepError> {
+        let invalid = || InvalidStepError {
+            step: step.to_string(),
+        };
+        let label = match parse_step(step) {
+            Some(Operation::Remove(label)) => {
+                let removed = self.lenses.remove(&label).map_err(|_| invalid())?;
+                if let Some(focal_length) = removed {
+                    let number = self.numbers.remove(&label).unwrap();
+                    self.slots[hash_string(&label) as usize].remove(number, focal_length as usize);
                 }
-                if !present {
-                    r#box.push((label.clone(), focal_length));
+                label
+            }
+            Some(Operation::Set((label, focal_length))) => {
+                let replaced = self
+                    .lenses
+                    .insert(&label, focal_length)
+                    .map_err(|_| invalid())?;
+                let slots = &mut self.slots[hash_string(&label) as usize];
+                match replaced {
+                    Some(old) => {
+                        slots.replace(self.numbers[&label], old as usize, focal_length as usize)
+                    }
+                    None => {
+                        let number = slots.push(focal_length as usize);
+                        self.numbers.insert(label.clone(), number);
+                    }
                 }
+                label
             }
-        }
+            None => return Err(invalid()),
+        };
+
+        let box_number = hash_string(&label);
+        let box_power = (box_number as usize + 1) * self.slots[box_number as usize].weighted;
+        let previous = std::mem::replace(&mut self.box_powers[box_number as usize], box_power);
+        self.power = self.power - previous + box_power;
+
+        Ok(box_number)
     }
+}
+
+impl LineSolver for FocusingPower {
+    type Total = usize;
+    type Error = InvalidStepError;
 
-    let mut acc = 0;
+    const SEPARATOR: u8 = b',';
 
-    for (i, r#box) in boxes.iter().enumerate() {
-        for (j, (_, focal_length)) in r#box.iter().enumerate() {
-            acc = acc + (i + 1) * (j + 1) * (*focal_length as usize);
+    fn feed(&mut self, step: &str) -> Result<(), InvalidStepError> {
+        self.apply(step).map(|_| ())
+    }
+
+    fn total(&self) -> usize {
+        self.power
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct TracedStep {
+    pub step: String,
+    // The box the step changed and its lenses from front to back afterwards
+    pub box_number: u8,
+    pub lenses: Vec<(String, u8)>,
+    pub focusing_power: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct InitializationTrace {
+    pub steps: Vec<TracedStep>,
+}
+
+impl InitializationTrace {
+    // The focusing power once the first n steps have been carried out, or None if there are fewer than n steps
+    pub fn focusing_power_after(&self, n: usize) -> Option<usize> {
+        match n {
+            0 => Some(0),
+            n => self.steps.get(n - 1).map(|step| step.focusing_power),
         }
     }
+}
 
-    acc
+pub fn day_15_trace_initialization_sequence(
+    initialization_sequence: &str,
+) -> Result<InitializationTrace, InvalidStepError> {
+    let mut library = FocusingPower::default();
+    let mut steps = Vec::new();
+
+    let initialization_sequence = Input::new(initialization_sequence);
+    for step in initialization_sequence
+        .as_str()
+        .split(',')
+        .map(|step| step.trim())
+    {
+        let box_number = library.apply(step)?;
+        steps.push(TracedStep {
+            step: step.to_string(),
+            box_number,
+            lenses: library
+                .lenses
+                .lenses(box_number)
+                .map(|(label, focal_length)| (label.to_string(), *focal_length))
+                .collect(),
+            focusing_power: library.power,
+        });
+    }
+
+    Ok(InitializationTrace { steps })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::streaming::{final_total, running_totals};
+
     #[test]
     fn test_hash_string() {
         assert_eq!(30, hash_string("rn=1"));
@@ -162,6 +736,88 @@ mod tests {
         assert_eq!(231, hash_string("ot=7"));
     }
 
+    #[test]
+    fn test_hash_function() {
+        let standard = HashFunction::standard();
+        assert_eq!(Some(52), standard.hash("HASH"));
+        assert_eq!(Some(0), standard.hash(""));
+        assert_eq!(None, standard.hash("\u{7f}\u{126}"));
+        assert_eq!(Some(17 * 127 % 256), standard.hash("\u{7f}"));
+
+        let hash_function = HashFunction::new(31, 1000).unwrap();
+        assert_eq!(Some(255), hash_function.hash("ab"));
+
+        let hash_function = HashFunction::new(usize::MAX, usize::MAX - 1).unwrap();
+        assert_eq!(Some(97), hash_function.hash("a"));
+
+        assert_eq!(
+            Err(HashFunctionError::ZeroModulus),
+            HashFunction::new(17, 0)
+        );
+    }
+
+    #[test]
+    fn test_colliding_labels() {
+        let standard = HashFunction::standard();
+        let labels = standard
+            .colliding_labels(3, "ab", 10)
+            .unwrap()
+            .collect::<Vec<_>>();
+
+        let mut expected = Vec::new();
+        for length in 1..=10 {
+            for n in 0..1 << length {
+                let label = (0..length)
+                    .rev()
+                    .map(|bit| if n >> bit & 1 == 0 { 'a' } else { 'b' })
+                    .collect::<String>();
+                if standard.hash(&label) == Some(3) {
+                    expected.push(label);
+                }
+            }
+        }
+        assert!(!expected.is_empty());
+        assert_eq!(expected, labels);
+
+        let hash_function = HashFunction::new(3, 10).unwrap();
+        assert_eq!(
+            vec!["a", "aca", "ccc"],
+            hash_function
+                .colliding_labels(1, "ac", 3)
+                .unwrap()
+                .collect::<Vec<_>>()
+        );
+        // Nothing can hash to a box beyond the modulus
+        assert_eq!(
+            None,
+            hash_function.colliding_labels(10, "ac", 5).unwrap().next()
+        );
+        assert_eq!(None, standard.colliding_labels(0, "", 5).unwrap().next());
+
+        // The search would need a table far too large to hold
+        let huge = HashFunction::new(17, usize::MAX - 1).unwrap();
+        assert_eq!(
+            Some(HashFunctionError::SearchTooLarge {
+                modulus: usize::MAX - 1,
+                max_length: 1
+            }),
+            huge.colliding_labels(0, "ab", 1).err()
+        );
+        assert!(standard.colliding_labels(0, "ab", usize::MAX).is_err());
+    }
+
+    #[test]
+    fn test_day_15_adversarial_sequence() {
+        let sequence = day_15_adversarial_sequence(7, 50);
+        assert_eq!(100, sequence.split(',').count());
+
+        let trace = day_15_trace_initialization_sequence(&sequence).unwrap();
+        assert!(trace.steps.iter().all(|step| step.box_number == 7));
+        assert_eq!(50, trace.steps[49].lenses.len());
+        assert_eq!(49, trace.steps[50].lenses.len());
+        assert_eq!(Some(0), trace.focusing_power_after(100));
+    }
+
     #[test]
     fn test_day_15_determine_verfication_number() {
         assert_eq!(
@@ -181,4 +837,140 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn test_lens_map() {
+        let mut lenses = LensMap::new();
+        assert!(lenses.is_empty());
+
+        // rn, cm and the empty label all go in box 0
+        assert_eq!(None, lenses.insert("rn", 1).unwrap());
+        assert_eq!(None, lenses.insert("cm", 2).unwrap());
+        assert_eq!(None, lenses.insert("", 3).unwrap());
+        assert_eq!(None, lenses.insert("qp", 4).unwrap());
+        assert_eq!(Some(1), lenses.insert("rn", 5).unwrap());
+        assert_eq!(4, lenses.len());
+        assert_eq!(Some(&5), lenses.get("rn"));
+        assert_eq!(
+            vec![("rn", &5), ("cm", &2), ("", &3)],
+            lenses.lenses(0).collect::<Vec<_>>()
+        );
+        assert_eq!(vec![("qp", &4)], lenses.lenses(1).collect::<Vec<_>>());
+
+        assert_eq!(Some(2), lenses.remove("cm").unwrap());
+        assert_eq!(None, lenses.remove("cm").unwrap());
+        assert_eq!(None, lenses.insert("cm", 6).unwrap());
+        assert_eq!(Some(5), lenses.remove("rn").unwrap());
+        assert_eq!(
+            vec![("", &3), ("cm", &6)],
+            lenses.lenses(0).collect::<Vec<_>>()
+        );
+        assert_eq!(Some(6), lenses.remove("cm").unwrap());
+        assert_eq!(Some(3), lenses.remove("").unwrap());
+        assert_eq!(None, lenses.lenses(0).next());
+        assert_eq!(Some(4), lenses.remove("qp").unwrap());
+        assert!(lenses.is_empty());
+
+        // Labels which are not ASCII have no box
+        let label = NonAsciiLabelError {
+            label: "é".to_string(),
+        };
+        assert_eq!(Err(label), lenses.insert("é", 7));
+        assert_eq!(
+            Err(NonAsciiLabelError {
+                label: "é".to_string()
+            }),
+            lenses.remove("é")
+        );
+        assert!(lenses.is_empty());
+    }
+
+    #[test]
+    fn test_day_15_trace_initialization_sequence() {
+        let trace = day_15_trace_initialization_sequence(
+            "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7",
+        )
+        .unwrap();
+
+        let lenses = |lenses: &[(&str, u8)]| {
+            lenses
+                .iter()
+                .map(|(label, focal_length)| (label.to_string(), *focal_length))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            TracedStep {
+                step: "cm=2".to_string(),
+                box_number: 0,
+                lenses: lenses(&[("rn", 1), ("cm", 2)]),
+                focusing_power: 11
+            },
+            trace.steps[3]
+        );
+        assert_eq!(lenses(&[]), trace.steps[4].lenses);
+        assert_eq!(lenses(&[("ot", 9), ("ab", 5)]), trace.steps[8].lenses);
+        assert_eq!(
+            lenses(&[("ot", 7), ("ab", 5), ("pc", 6)]),
+            trace.steps[10].lenses
+        );
+        assert_eq!(
+            vec![0, 0, 1, 0, 1, 3, 3, 3, 3, 3, 3],
+            trace
+                .steps
+                .iter()
+                .map(|step| step.box_number)
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(Some(0), trace.focusing_power_after(0));
+        assert_eq!(Some(5), trace.focusing_power_after(5));
+        assert_eq!(Some(145), trace.focusing_power_after(11));
+        assert_eq!(None, trace.focusing_power_after(12));
+
+        assert_eq!(
+            Err(InvalidStepError {
+                step: "cm".to_string()
+            }),
+            day_15_trace_initialization_sequence("rn=1,cm")
+        );
+    }
+
+    #[test]
+    fn test_streaming_initialization_sequence() {
+        let sequence = "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7\n";
+        assert_eq!(
+            Some(1320),
+            final_total(sequence.as_bytes(), VerificationNumber::default()).unwrap()
+        );
+        assert_eq!(
+            vec![1, 1, 7, 11, 5, 21, 93, 153, 81, 153, 145],
+            running_totals(sequence.as_bytes(), FocusingPower::default())
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        );
+        // Updating box powers step by step agrees with walking every box
+        // through inserts, replacements and removals from anywhere in one crowded box
+        let labels = HashFunction::standard()
+            .colliding_labels(7, "abc", 8)
+            .unwrap()
+            .take(8)
+            .collect::<Vec<_>>();
+        let mut library = FocusingPower::default();
+        let mut random: usize = 1;
+        for _ in 0..500 {
+            random = random
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let label = &labels[(random >> 33) % labels.len()];
+            let step = match (random >> 40) % 10 {
+                0..=3 => format!("{}-", label),
+                focal_length => format!("{}={}", label, focal_length),
+            };
+            library.apply(&step).unwrap();
+            assert_eq!(library.lenses.focusing_power(), library.power);
+        }
+
+        assert!(final_total("rn=1,cm".as_bytes(), FocusingPower::default()).is_err());
+        assert!(final_total("rn=x".as_bytes(), FocusingPower::default()).is_err());
+    }
 }
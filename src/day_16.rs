@@ -1,133 +1,683 @@
 use crate::utils::{move_2d, parse_2d_matrix};
 
-use std::cmp::max;
-use std::collections::{HashSet, VecDeque};
+use std::cmp::min;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash)]
-enum Direction {
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Direction {
     Up,
     Down,
     Left,
     Right,
 }
 
+impl Direction {
+    fn delta(&self) -> (isize, isize) {
+        match self {
+            Direction::Up => (-1, 0),
+            Direction::Down => (1, 0),
+            Direction::Left => (0, -1),
+            Direction::Right => (0, 1),
+        }
+    }
+
+    fn arrow(&self) -> char {
+        match self {
+            Direction::Up => '^',
+            Direction::Down => 'v',
+            Direction::Left => '<',
+            Direction::Right => '>',
+        }
+    }
+}
+
+// A beam entering a tile, travelling in a direction
+pub type BeamState = ((usize, usize), Direction);
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+#[derive(Debug, PartialEq)]
+pub struct UnknownElementError {
+    // Every unrecognised glyph in the layout and its position
+    pub elements: Vec<(char, (usize, usize))>,
+}
+
+impl fmt::Display for UnknownElementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown optical elements:")?;
+        for (glyph, (i, j)) in &self.elements {
+            write!(f, " '{}' at ({}, {})", glyph, i, j)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LayoutError {
+    UnknownElements(UnknownElementError),
+    // A row with a different number of tiles than the first
+    RaggedRow {
+        row: usize,
+        length: usize,
+        expected: usize,
+    },
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutError::UnknownElements(error) => write!(f, "{}", error),
+            LayoutError::RaggedRow {
+                row,
+                length,
+                expected,
+            } => write!(
+                f,
+                "Row {} has {} tiles instead of {}",
+                row, length, expected
+            ),
+        }
+    }
+}
+
+// Table from each glyph to the directions a beam leaves the tile in, indexed by the direction it entered in
+pub struct OpticalElements {
+    table: HashMap<char, [Vec<Direction>; 4]>,
+}
+
+impl OpticalElements {
+    // No elements at all, to add them to with `with_element`
+    pub fn new() -> OpticalElements {
+        OpticalElements {
+            table: HashMap::new(),
+        }
+    }
+
+    // The empty space, mirrors and splitters from the puzzle
+    pub fn standard() -> OpticalElements {
+        OpticalElements::new()
+            .with_element(
+                '.',
+                [
+                    vec![Direction::Up],
+                    vec![Direction::Down],
+                    vec![Direction::Left],
+                    vec![Direction::Right],
+                ],
+            )
+            .with_element(
+                '/',
+                [
+                    vec![Direction::Right],
+                    vec![Direction::Left],
+                    vec![Direction::Down],
+                    vec![Direction::Up],
+                ],
+            )
+            .with_element(
+                '\\',
+                [
+                    vec![Direction::Left],
+                    vec![Direction::Right],
+                    vec![Direction::Up],
+                    vec![Direction::Down],
+                ],
+            )
+            .with_element(
+                '|',
+                [
+                    vec![Direction::Up],
+                    vec![Direction::Down],
+                    vec![Direction::Up, Direction::Down],
+                    vec![Direction::Up, Direction::Down],
+                ],
+            )
+            .with_element(
+                '-',
+                [
+                    vec![Direction::Left, Direction::Right],
+                    vec![Direction::Left, Direction::Right],
+                    vec![Direction::Left],
+                    vec![Direction::Right],
+                ],
+            )
+    }
+
+    // Outgoing directions for a beam entering Up, Down, Left and Right respectively.
+    // An empty list absorbs the beam.
+    pub fn with_element(
+        mut self,
+        glyph: char,
+        transitions: [Vec<Direction>; 4],
+    ) -> OpticalElements {
+        self.table.insert(glyph, transitions);
+        self
+    }
+
+    pub fn validate(&self, layout: &[Vec<char>]) -> Result<(), UnknownElementError> {
+        let mut elements = Vec::new();
+        for (i, row) in layout.iter().enumerate() {
+            for (j, tile) in row.iter().enumerate() {
+                if !self.table.contains_key(tile) {
+                    elements.push((*tile, (i, j)));
+                }
+            }
+        }
+
+        if elements.is_empty() {
+            Ok(())
+        } else {
+            Err(UnknownElementError { elements })
+        }
+    }
+
+    // Directions a beam travelling in the given direction leaves the tile in.
+    // The tile must have been validated.
+    fn next_directions(&self, tile: char, direction: Direction) -> &[Direction] {
+        &self.table[&tile][direction as usize]
+    }
+}
+
+impl Default for OpticalElements {
+    fn default() -> OpticalElements {
+        OpticalElements::new()
+    }
+}
+
+fn parse_layout(input: &str, elements: &OpticalElements) -> Result<Vec<Vec<char>>, LayoutError> {
+    let layout = parse_2d_matrix(input);
+    let (_, expected) = layout_size(&layout);
+    if let Some((row, length)) = layout
+        .iter()
+        .map(|row| row.len())
+        .enumerate()
+        .find(|(_, length)| *length != expected)
+    {
+        return Err(LayoutError::RaggedRow {
+            row,
+            length,
+            expected,
+        });
+    }
+    elements
+        .validate(&layout)
+        .map_err(LayoutError::UnknownElements)?;
+
+    Ok(layout)
+}
+
+// Rows and columns of the layout, which has none of either if the input was empty
+fn layout_size(layout: &[Vec<char>]) -> (usize, usize) {
+    (layout.len(), layout.first().map_or(0, |row| row.len()))
+}
+
+// Directions of every beam entering each tile
+fn trace_beams(
+    layout: &[Vec<char>],
+    elements: &OpticalElements,
+    size: (usize, usize),
+    start_state: BeamState,
+) -> Vec<Vec<Vec<Direction>>> {
+    let (n, m) = size;
+
+    let mut beams = vec![vec![Vec::new(); m]; n];
+    let mut explored_states: HashSet<BeamState> = HashSet::new();
+    // A beam starting outside the contraption never enters it
+    let ((i, j), _) = start_state;
+    let mut next = if i < n && j < m {
+        VecDeque::from([start_state])
+    } else {
+        VecDeque::new()
+    };
+
+    while let Some(((i, j), direction)) = next.pop_front() {
+        if !explored_states.insert(((i, j), direction)) {
+            continue;
+        }
+        beams[i][j].push(direction);
+
+        for &direction in elements.next_directions(layout[i][j], direction) {
+            match move_2d((i, j), direction.delta(), size) {
+                Ok((i, j)) => {
+                    if !explored_states.contains(&((i, j), direction)) {
+                        next.push_back(((i, j), direction));
+                    }
+                }
+                Err(_) => continue,
+            }
+        }
+    }
+
+    for row in beams.iter_mut() {
+        for directions in row.iter_mut() {
+            directions.sort_by_key(|direction| *direction as usize);
+        }
+    }
+
+    beams
+}
+
 fn count_energized_tiles(
-    layout: &Vec<Vec<char>>,
+    layout: &[Vec<char>],
+    elements: &OpticalElements,
     size: (usize, usize),
-    start_state: ((usize, usize), Direction),
+    start_state: BeamState,
 ) -> usize {
-    let (n, m) = size;
+    trace_beams(layout, elements, size, start_state)
+        .iter()
+        .flatten()
+        .filter(|directions| !directions.is_empty())
+        .count()
+}
+
+// Empty tiles show the direction of the beam passing through them, or # if there are several
+fn render_beams(layout: &[Vec<char>], beams: &[Vec<Vec<Direction>>]) -> String {
+    let mut ret = String::new();
+
+    for (row, beam_row) in layout.iter().zip(beams) {
+        for (tile, directions) in row.iter().zip(beam_row) {
+            ret.push(match (tile, directions.len()) {
+                ('.', 0) => '.',
+                ('.', 1) => directions[0].arrow(),
+                ('.', _) => '#',
+                (tile, _) => *tile,
+            });
+        }
+        ret.push('\n');
+    }
+
+    ret
+}
+
+const SVG_TILE_SIZE: usize = 16;
+
+fn render_beams_svg(layout: &[Vec<char>], beams: &[Vec<Vec<Direction>>]) -> String {
+    let n = layout.len();
+    let m = layout.first().map_or(0, |row| row.len());
+    let half = SVG_TILE_SIZE / 2;
+
+    let mut ret = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}">"#,
+        m * SVG_TILE_SIZE,
+        n * SVG_TILE_SIZE
+    );
+    ret.push_str(r#"<rect width="100%" height="100%" fill="black"/>"#);
+
+    for (i, (row, beam_row)) in layout.iter().zip(beams).enumerate() {
+        for (j, (tile, directions)) in row.iter().zip(beam_row).enumerate() {
+            let (x, y) = (j * SVG_TILE_SIZE, i * SVG_TILE_SIZE);
+            if !directions.is_empty() {
+                ret.push_str(&format!(
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="gold" fill-opacity="0.3"/>"#,
+                    x, y, SVG_TILE_SIZE, SVG_TILE_SIZE
+                ));
+            }
+            for direction in directions {
+                // Line from the tile's centre back towards the side the beam entered from
+                let (di, dj) = direction.delta();
+                ret.push_str(&format!(
+                    r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="gold"/>"#,
+                    x + half,
+                    y + half,
+                    (x + half) as isize - dj * half as isize,
+                    (y + half) as isize - di * half as isize
+                ));
+            }
+            let glyph = match tile {
+                '.' => continue,
+                '&' => "&amp;".to_string(),
+                '<' => "&lt;".to_string(),
+                '>' => "&gt;".to_string(),
+                '"' => "&quot;".to_string(),
+                tile => tile.to_string(),
+            };
+            ret.push_str(&format!(
+                r#"<text x="{}" y="{}" fill="white" font-family="monospace" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                x + half,
+                y + half,
+                glyph
+            ));
+        }
+    }
+
+    ret.push_str("</svg>");
+    ret
+}
 
-    let mut energized_squares: HashSet<(usize, usize)> = HashSet::new();
-    let mut explored_states: HashSet<((usize, usize), Direction)> = HashSet::new();
-    let mut next = VecDeque::from([start_state]);
+// Fixed size set of tiles, one bit per tile
+#[derive(Clone)]
+struct TileSet {
+    width: usize,
+    bits: Vec<u64>,
+}
+
+impl TileSet {
+    fn new(size: (usize, usize)) -> TileSet {
+        let (n, m) = size;
+        TileSet {
+            width: m,
+            bits: vec![0; (n * m).div_ceil(64)],
+        }
+    }
+
+    fn insert(&mut self, (i, j): (usize, usize)) {
+        let k = i * self.width + j;
+        self.bits[k / 64] |= 1 << (k % 64);
+    }
+
+    fn union_with(&mut self, other: &TileSet) {
+        for (a, b) in self.bits.iter_mut().zip(&other.bits) {
+            *a |= b;
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.bits
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+}
+
+// A straight run of beam, possibly bent by mirrors, from where it starts until it reaches a splitter or leaves the contraption.
+struct Segment {
+    tiles: TileSet,
+    // The splitter state the segment runs into, if any
+    end: Option<BeamState>,
+}
+
+fn trace_segment(
+    layout: &[Vec<char>],
+    elements: &OpticalElements,
+    size: (usize, usize),
+    start_state: BeamState,
+) -> Segment {
+    let mut tiles = TileSet::new(size);
+    let mut explored_states: HashSet<BeamState> = HashSet::new();
+    let ((mut i, mut j), mut direction) = start_state;
 
     loop {
-        if let Some(((i, j), direction)) = next.pop_front() {
-            explored_states.insert(((i, j), direction));
-            energized_squares.insert((i, j));
-
-            for next_state in match (layout[i][j], direction) {
-                ('.', Direction::Up) => vec![(move_2d((i, j), (-1, 0), (n, m)), Direction::Up)],
-                ('.', Direction::Down) => vec![(move_2d((i, j), (1, 0), (n, m)), Direction::Down)],
-                ('.', Direction::Left) => vec![(move_2d((i, j), (0, -1), (n, m)), Direction::Left)],
-                ('.', Direction::Right) => {
-                    vec![(move_2d((i, j), (0, 1), (n, m)), Direction::Right)]
+        // The beam is looping between mirrors
+        if !explored_states.insert(((i, j), direction)) {
+            return Segment { tiles, end: None };
+        }
+        tiles.insert((i, j));
+
+        let directions = elements.next_directions(layout[i][j], direction);
+        if directions.len() != 1 {
+            return Segment {
+                tiles,
+                end: Some(((i, j), direction)),
+            };
+        }
+
+        direction = directions[0];
+        match move_2d((i, j), direction.delta(), size) {
+            Ok(next) => (i, j) = next,
+            Err(_) => return Segment { tiles, end: None },
+        }
+    }
+}
+
+// Graph with a node for every state where the beam splits, joined by the segments between them.
+// Nodes are condensed into strongly connected components, each of which caches every tile energized from it.
+struct BeamGraph {
+    size: (usize, usize),
+    nodes: HashMap<BeamState, usize>,
+    component: Vec<usize>,
+    component_tiles: Vec<TileSet>,
+}
+
+impl BeamGraph {
+    fn new(layout: &[Vec<char>], elements: &OpticalElements, size: (usize, usize)) -> BeamGraph {
+        let mut nodes = HashMap::new();
+        let mut states = Vec::new();
+        for (i, row) in layout.iter().enumerate() {
+            for (j, tile) in row.iter().enumerate() {
+                for direction in DIRECTIONS {
+                    if elements.next_directions(*tile, direction).len() != 1 {
+                        nodes.insert(((i, j), direction), states.len());
+                        states.push(((i, j), direction));
+                    }
                 }
-                ('/', Direction::Up) => vec![(move_2d((i, j), (0, 1), (n, m)), Direction::Right)],
-                ('/', Direction::Down) => vec![(move_2d((i, j), (0, -1), (n, m)), Direction::Left)],
-                ('/', Direction::Left) => vec![(move_2d((i, j), (1, 0), (n, m)), Direction::Down)],
-                ('/', Direction::Right) => vec![(move_2d((i, j), (-1, 0), (n, m)), Direction::Up)],
-                ('\\', Direction::Up) => vec![(move_2d((i, j), (0, -1), (n, m)), Direction::Left)],
-                ('\\', Direction::Down) => {
-                    vec![(move_2d((i, j), (0, 1), (n, m)), Direction::Right)]
+            }
+        }
+
+        // Tiles energized by each node's outgoing segments and the nodes those segments run into
+        let mut node_tiles = Vec::new();
+        let mut edges = Vec::new();
+        for &((i, j), direction) in &states {
+            let mut tiles = TileSet::new(size);
+            tiles.insert((i, j));
+            let mut successors = Vec::new();
+            for &direction in elements.next_directions(layout[i][j], direction) {
+                if let Ok(next) = move_2d((i, j), direction.delta(), size) {
+                    let segment = trace_segment(layout, elements, size, (next, direction));
+                    tiles.union_with(&segment.tiles);
+                    if let Some(end) = segment.end {
+                        successors.push(nodes[&end]);
+                    }
                 }
-                ('\\', Direction::Left) => vec![(move_2d((i, j), (-1, 0), (n, m)), Direction::Up)],
-                ('\\', Direction::Right) => {
-                    vec![(move_2d((i, j), (1, 0), (n, m)), Direction::Down)]
+            }
+            node_tiles.push(tiles);
+            edges.push(successors);
+        }
+
+        let (component, n_components) = strongly_connected_components(&edges);
+
+        // Tarjan's algorithm numbers components in reverse topological order,
+        // so every component's successors are complete before it is.
+        let mut members = vec![Vec::new(); n_components];
+        for (node, &c) in component.iter().enumerate() {
+            members[c].push(node);
+        }
+        let mut component_tiles: Vec<TileSet> = Vec::with_capacity(n_components);
+        for (c, members) in members.iter().enumerate() {
+            let mut tiles = TileSet::new(size);
+            for &node in members {
+                tiles.union_with(&node_tiles[node]);
+                for &successor in &edges[node] {
+                    if component[successor] != c {
+                        tiles.union_with(&component_tiles[component[successor]]);
+                    }
                 }
-                ('|', Direction::Up) => vec![(move_2d((i, j), (-1, 0), (n, m)), Direction::Up)],
-                ('|', Direction::Down) => vec![(move_2d((i, j), (1, 0), (n, m)), Direction::Down)],
-                ('|', Direction::Left) => vec![
-                    (move_2d((i, j), (-1, 0), (n, m)), Direction::Up),
-                    (move_2d((i, j), (1, 0), (n, m)), Direction::Down),
-                ],
-                ('|', Direction::Right) => vec![
-                    (move_2d((i, j), (-1, 0), (n, m)), Direction::Up),
-                    (move_2d((i, j), (1, 0), (n, m)), Direction::Down),
-                ],
-                ('-', Direction::Up) => vec![
-                    (move_2d((i, j), (0, -1), (n, m)), Direction::Left),
-                    (move_2d((i, j), (0, 1), (n, m)), Direction::Right),
-                ],
-                ('-', Direction::Down) => vec![
-                    (move_2d((i, j), (0, -1), (n, m)), Direction::Left),
-                    (move_2d((i, j), (0, 1), (n, m)), Direction::Right),
-                ],
-                ('-', Direction::Left) => vec![(move_2d((i, j), (0, -1), (n, m)), Direction::Left)],
-                ('-', Direction::Right) => {
-                    vec![(move_2d((i, j), (0, 1), (n, m)), Direction::Right)]
+            }
+            component_tiles.push(tiles);
+        }
+
+        BeamGraph {
+            size,
+            nodes,
+            component,
+            component_tiles,
+        }
+    }
+
+    fn count_energized_tiles(
+        &self,
+        layout: &[Vec<char>],
+        elements: &OpticalElements,
+        start_state: BeamState,
+    ) -> usize {
+        let mut segment = trace_segment(layout, elements, self.size, start_state);
+        if let Some(end) = segment.end {
+            segment
+                .tiles
+                .union_with(&self.component_tiles[self.component[self.nodes[&end]]]);
+        }
+        segment.tiles.len()
+    }
+}
+
+// Iterative Tarjan's algorithm, returning the component of each node and the number of components
+fn strongly_connected_components(edges: &[Vec<usize>]) -> (Vec<usize>, usize) {
+    let n = edges.len();
+
+    let mut index = vec![usize::MAX; n];
+    let mut low_link = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut stack = Vec::new();
+    let mut component = vec![usize::MAX; n];
+    let mut n_components = 0;
+    let mut next_index = 0;
+
+    for root in 0..n {
+        if index[root] != usize::MAX {
+            continue;
+        }
+
+        // (node, position of the next edge to explore)
+        let mut call_stack = vec![(root, 0)];
+        index[root] = next_index;
+        low_link[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        while let Some((node, k)) = call_stack.pop() {
+            if k < edges[node].len() {
+                call_stack.push((node, k + 1));
+
+                let successor = edges[node][k];
+                if index[successor] == usize::MAX {
+                    index[successor] = next_index;
+                    low_link[successor] = next_index;
+                    next_index += 1;
+                    stack.push(successor);
+                    on_stack[successor] = true;
+                    call_stack.push((successor, 0));
+                } else if on_stack[successor] {
+                    low_link[node] = min(low_link[node], index[successor]);
                 }
-                (_, _) => unreachable!(),
+                continue;
             }
-            .iter()
-            {
-                match next_state {
-                    (Ok((i, j)), direction) => {
-                        if !explored_states.contains(&((*i, *j), *direction)) {
-                            next.push_back(((*i, *j), *direction));
-                        }
+
+            if low_link[node] == index[node] {
+                loop {
+                    let member = stack.pop().unwrap();
+                    on_stack[member] = false;
+                    component[member] = n_components;
+                    if member == node {
+                        break;
                     }
-                    (Err(_), _) => continue,
                 }
+                n_components += 1;
+            }
+
+            if let Some(&(parent, _)) = call_stack.last() {
+                low_link[parent] = min(low_link[parent], low_link[node]);
             }
-        } else {
-            break;
         }
     }
 
-    energized_squares.len()
+    (component, n_components)
 }
 
-pub fn day_16_count_energized_tiles(input: &str) -> usize {
-    let layout = parse_2d_matrix(input);
+pub fn day_16_count_energized_tiles(input: &str) -> Result<usize, LayoutError> {
+    day_16_count_energized_tiles_with_elements(input, &OpticalElements::standard())
+}
 
-    let n = layout.len();
-    let m = layout[0].len();
+pub fn day_16_count_energized_tiles_with_elements(
+    input: &str,
+    elements: &OpticalElements,
+) -> Result<usize, LayoutError> {
+    let layout = parse_layout(input, elements)?;
+    let size = layout_size(&layout);
 
-    count_energized_tiles(&layout, (n, m), ((0, 0), Direction::Right))
+    Ok(count_energized_tiles(
+        &layout,
+        elements,
+        size,
+        ((0, 0), Direction::Right),
+    ))
 }
 
-pub fn day_16_count_most_energized_tiles(input: &str) -> usize {
-    let layout = parse_2d_matrix(input);
+pub fn day_16_energized_directions(
+    input: &str,
+    elements: &OpticalElements,
+    start_state: BeamState,
+) -> Result<Vec<Vec<Vec<Direction>>>, LayoutError> {
+    let layout = parse_layout(input, elements)?;
+    let size = layout_size(&layout);
 
-    let n = layout.len();
-    let m = layout[0].len();
+    Ok(trace_beams(&layout, elements, size, start_state))
+}
+
+pub fn day_16_render_beams(
+    input: &str,
+    elements: &OpticalElements,
+    start_state: BeamState,
+) -> Result<String, LayoutError> {
+    let layout = parse_layout(input, elements)?;
+    let size = layout_size(&layout);
+
+    Ok(render_beams(
+        &layout,
+        &trace_beams(&layout, elements, size, start_state),
+    ))
+}
+
+pub fn day_16_render_beams_svg(
+    input: &str,
+    elements: &OpticalElements,
+    start_state: BeamState,
+) -> Result<String, LayoutError> {
+    let layout = parse_layout(input, elements)?;
+    let size = layout_size(&layout);
+
+    Ok(render_beams_svg(
+        &layout,
+        &trace_beams(&layout, elements, size, start_state),
+    ))
+}
+
+// Every start state on the edge of the contraption, from most to least energized tiles
+pub fn day_16_rank_entry_points(
+    input: &str,
+    elements: &OpticalElements,
+) -> Result<Vec<(BeamState, usize)>, LayoutError> {
+    let layout = parse_layout(input, elements)?;
 
-    let mut max_energized_tiles = 0;
+    let (n, m) = layout_size(&layout);
 
+    let graph = BeamGraph::new(&layout, elements, (n, m));
+
+    let mut entry_points = Vec::new();
     for i in 0..n {
-        max_energized_tiles = max(
-            max_energized_tiles,
-            count_energized_tiles(&layout, (n, m), ((i, 0), Direction::Right)),
-        );
-        max_energized_tiles = max(
-            max_energized_tiles,
-            count_energized_tiles(&layout, (n, m), ((i, m - 1), Direction::Left)),
-        );
+        entry_points.push(((i, 0), Direction::Right));
+        entry_points.push(((i, m - 1), Direction::Left));
     }
     for j in 0..m {
-        max_energized_tiles = max(
-            max_energized_tiles,
-            count_energized_tiles(&layout, (n, m), ((0, j), Direction::Down)),
-        );
-        max_energized_tiles = max(
-            max_energized_tiles,
-            count_energized_tiles(&layout, (n, m), ((n - 1, j), Direction::Up)),
-        );
+        entry_points.push(((0, j), Direction::Down));
+        entry_points.push(((n - 1, j), Direction::Up));
     }
 
-    max_energized_tiles
+    let mut ranking = entry_points
+        .into_iter()
+        .map(|start_state| {
+            (
+                start_state,
+                graph.count_energized_tiles(&layout, elements, start_state),
+            )
+        })
+        .collect::<Vec<_>>();
+    ranking.sort_by_key(|(_, energized_tiles)| std::cmp::Reverse(*energized_tiles));
+
+    Ok(ranking)
+}
+
+pub fn day_16_count_most_energized_tiles(input: &str) -> Result<usize, LayoutError> {
+    Ok(
+        day_16_rank_entry_points(input, &OpticalElements::standard())?
+            .first()
+            .map_or(0, |(_, energized_tiles)| *energized_tiles),
+    )
 }
 
 #[cfg(test)]
@@ -147,11 +697,231 @@ mod tests {
 
     #[test]
     fn test_day_16_count_energized_tiles() {
-        assert_eq!(46, day_16_count_energized_tiles(EXAMPLE));
+        assert_eq!(Ok(46), day_16_count_energized_tiles(EXAMPLE));
+        assert_eq!(
+            Err(LayoutError::UnknownElements(UnknownElementError {
+                elements: vec![('x', (0, 1))]
+            })),
+            day_16_count_energized_tiles(".x.")
+        );
     }
 
     #[test]
     fn test_day_16_count_most_energized_tiles() {
-        assert_eq!(51, day_16_count_most_energized_tiles(EXAMPLE));
+        assert_eq!(Ok(51), day_16_count_most_energized_tiles(EXAMPLE));
+        assert!(day_16_count_most_energized_tiles(".x.").is_err());
+    }
+
+    #[test]
+    fn test_beam_graph_matches_simulation() {
+        for layout in [
+            parse_2d_matrix(EXAMPLE),
+            // Splitters feeding back into each other
+            parse_2d_matrix(
+                r#"/.-.\
+                .\.|/
+                |.-..
+                \.../"#,
+            ),
+        ] {
+            let elements = OpticalElements::standard();
+            let (n, m) = (layout.len(), layout[0].len());
+            let graph = BeamGraph::new(&layout, &elements, (n, m));
+
+            for i in 0..n {
+                for j in 0..m {
+                    for direction in DIRECTIONS {
+                        assert_eq!(
+                            count_energized_tiles(&layout, &elements, (n, m), ((i, j), direction)),
+                            graph.count_energized_tiles(&layout, &elements, ((i, j), direction))
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_day_16_energized_directions() {
+        let beams = day_16_energized_directions(
+            EXAMPLE,
+            &OpticalElements::standard(),
+            ((0, 0), Direction::Right),
+        )
+        .unwrap();
+
+        assert_eq!(vec![Direction::Right], beams[0][0]);
+        // The splitter is entered from both sides
+        assert_eq!(vec![Direction::Left, Direction::Right], beams[0][1]);
+        assert_eq!(vec![Direction::Up], beams[0][5]);
+        assert_eq!(Vec::<Direction>::new(), beams[0][6]);
+        assert_eq!(vec![Direction::Down, Direction::Right], beams[6][5]);
+    }
+
+    #[test]
+    fn test_day_16_render_beams() {
+        assert_eq!(
+            r#">|<<<\....
+|v-.\^....
+.v...|->>>
+.v...v^.|.
+.v...v^...
+.v...v^..\
+.v../#\\..
+<->-/vv|..
+.|<<<#-|.\
+.v//.|.v..
+"#,
+            day_16_render_beams(
+                EXAMPLE,
+                &OpticalElements::standard(),
+                ((0, 0), Direction::Right)
+            )
+            .unwrap()
+        );
+
+        let svg = day_16_render_beams_svg(
+            EXAMPLE,
+            &OpticalElements::standard(),
+            ((0, 0), Direction::Right),
+        )
+        .unwrap();
+        assert!(
+            svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="160" height="160">"#)
+        );
+        assert_eq!(46, svg.matches(r#"fill="gold""#).count());
+        assert!(svg.ends_with("</svg>"));
+    }
+
+    #[test]
+    fn test_day_16_rank_entry_points() {
+        let ranking = day_16_rank_entry_points(EXAMPLE, &OpticalElements::standard()).unwrap();
+
+        assert_eq!(40, ranking.len());
+        assert_eq!((((0, 3), Direction::Down), 51), ranking[0]);
+        assert!(ranking.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+    }
+
+    #[test]
+    fn test_custom_optical_elements() {
+        let elements = OpticalElements::standard()
+            // Absorber
+            .with_element('#', [vec![], vec![], vec![], vec![]])
+            // 4-way splitter
+            .with_element(
+                '+',
+                [
+                    DIRECTIONS.to_vec(),
+                    DIRECTIONS.to_vec(),
+                    DIRECTIONS.to_vec(),
+                    DIRECTIONS.to_vec(),
+                ],
+            )
+            // One-way mirror, reflecting beams travelling right upwards and letting the rest pass
+            .with_element(
+                'd',
+                [
+                    vec![Direction::Up],
+                    vec![Direction::Down],
+                    vec![Direction::Left],
+                    vec![Direction::Up],
+                ],
+            );
+
+        assert_eq!(
+            Ok(2),
+            day_16_count_energized_tiles_with_elements(".#.\n...", &elements)
+        );
+        assert_eq!(
+            Ok(vec![vec![0, 1, 0], vec![2, 1, 1], vec![0, 1, 0]]),
+            day_16_energized_directions("...\n.+.\n...", &elements, ((1, 0), Direction::Right))
+                .map(|beams| beams
+                    .iter()
+                    .map(|row| row.iter().map(|directions| directions.len()).collect())
+                    .collect::<Vec<Vec<_>>>())
+        );
+        assert_eq!(
+            Ok("..^\n>>d\n".to_string()),
+            day_16_render_beams("...\n..d", &elements, ((1, 0), Direction::Right))
+        );
+        assert_eq!(
+            Ok("..v\n..d\n".to_string()),
+            day_16_render_beams("...\n..d", &elements, ((0, 2), Direction::Down))
+        );
+    }
+
+    #[test]
+    fn test_svg_escapes_glyphs() {
+        let elements = OpticalElements::standard()
+            .with_element('<', [vec![], vec![], vec![], vec![]])
+            .with_element('&', [vec![], vec![], vec![], vec![]]);
+        let svg = day_16_render_beams_svg(".<&\\", &elements, ((0, 0), Direction::Right)).unwrap();
+        assert!(svg.contains(">&lt;</text>"));
+        assert!(svg.contains(">&amp;</text>"));
+        assert!(svg.contains(">\\</text>"));
+        assert!(!svg.contains("<<") && !svg.contains(">&<"));
+    }
+
+    #[test]
+    fn test_empty_layout() {
+        let elements = OpticalElements::standard();
+        let start = ((0, 0), Direction::Right);
+        assert_eq!(
+            Ok(0),
+            day_16_count_energized_tiles_with_elements("", &elements)
+        );
+        assert_eq!(
+            Ok(vec![]),
+            day_16_energized_directions("\n", &elements, start)
+        );
+        assert_eq!(
+            Ok("".to_string()),
+            day_16_render_beams("", &elements, start)
+        );
+        assert!(day_16_render_beams_svg("", &elements, start).is_ok());
+        assert_eq!(Ok(vec![]), day_16_rank_entry_points("", &elements));
+        // Starting outside the contraption energizes nothing
+        assert_eq!(
+            Ok("..\n".to_string()),
+            day_16_render_beams("..", &elements, ((1, 0), Direction::Right))
+        );
+    }
+
+    #[test]
+    fn test_default_optical_elements() {
+        // Like new, the default table has no elements
+        assert!(OpticalElements::default().validate(&[vec!['.']]).is_err());
+        assert!(OpticalElements::new().validate(&[vec!['.']]).is_err());
+    }
+
+    #[test]
+    fn test_unknown_optical_elements() {
+        assert_eq!(
+            Err(LayoutError::UnknownElements(UnknownElementError {
+                elements: vec![('#', (0, 1)), ('?', (1, 2))]
+            })),
+            day_16_count_energized_tiles_with_elements(".#.\n..?", &OpticalElements::standard())
+        );
+    }
+
+    #[test]
+    fn test_ragged_layout() {
+        let elements = OpticalElements::standard();
+        assert_eq!(
+            Err(LayoutError::RaggedRow {
+                row: 1,
+                length: 1,
+                expected: 3
+            }),
+            day_16_rank_entry_points("...\n.", &elements)
+        );
+        assert_eq!(
+            Err(LayoutError::RaggedRow {
+                row: 2,
+                length: 4,
+                expected: 3
+            }),
+            day_16_count_energized_tiles_with_elements("...\n...\n....", &elements)
+        );
     }
 }
@@ -1,3 +1,4 @@
+use crate::input::Input;
 use crate::utils::print_2d_matrix;
 
 use std::cmp::{max, min};
@@ -6,11 +7,8 @@ use std::collections::{HashSet, VecDeque};
 fn parse_dig_plan_part_1(input: &str) -> Vec<(char, usize)> {
     let mut dig_plan = Vec::new();
 
-    for line in input.split("\n").map(|line| line.trim()) {
-        if line == "" {
-            continue;
-        }
-
+    let input = Input::new(input);
+    for line in input.lines() {
         let mut line_iterator = line.split_whitespace();
         let direction = line_iterator.next().unwrap().parse::<char>().unwrap();
         let distance = line_iterator.next().unwrap().parse::<usize>().unwrap();
@@ -114,11 +112,8 @@ pub fn day_18_find_lagoon_capacity(input: &str) -> usize {
 fn parse_dig_plan_part_2(input: &str) -> Vec<(char, usize)> {
     let mut dig_plan = Vec::new();
 
-    for line in input.split("\n").map(|line| line.trim()) {
-        if line == "" {
-            continue;
-        }
-
+    let input = Input::new(input);
+    for line in input.lines() {
         let mut line_iterator = line.split_whitespace();
         let colour = line_iterator.nth(2).unwrap();
 
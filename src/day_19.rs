@@ -2,6 +2,8 @@ use regex::Regex;
 
 use std::collections::{HashMap, HashSet};
 
+use crate::input::Input;
+
 type MachinePart = [usize; 4];
 
 type Workflows<'a> = HashMap<&'a str, (Vec<WorkflowRule<'a>>, &'a str)>;
@@ -9,20 +11,16 @@ type Workflows<'a> = HashMap<&'a str, (Vec<WorkflowRule<'a>>, &'a str)>;
 #[derive(Debug)]
 struct WorkflowRule<'a>(char, char, usize, &'a str);
 
-fn parse_input(input: &str) -> (Workflows, Vec<MachinePart>) {
-    let mut input_iterator = input.split("\n").map(|line| line.trim());
+// Workflows then parts, as two sections
+fn parse_input(input: &Input) -> (Workflows<'_>, Vec<MachinePart>) {
+    let mut sections = input.sections();
 
     let mut workflows: Workflows = HashMap::new();
 
     let workflow_re = Regex::new(r"(\w+)\{(.*),(\w+)\}").unwrap();
     let rule_re = Regex::new(r"(\w+)([><])(\d+):(\w+)").unwrap();
 
-    loop {
-        let workflow = input_iterator.next().unwrap();
-        if workflow == "" {
-            break;
-        }
-
+    for workflow in sections.next().unwrap_or("").split('\n') {
         let (_, [name, rules, dump]) = workflow_re
             .captures(workflow)
             .expect("Unable to parse workflow")
@@ -47,22 +45,19 @@ fn parse_input(input: &str) -> (Workflows, Vec<MachinePart>) {
 
     let part_re = Regex::new(r"\{x=(\d+),m=(\d+),a=(\d+),s=(\d+)\}").unwrap();
 
-    loop {
-        if let Some(part) = input_iterator.next() {
-            if part == "" {
-                break;
-            }
-            let (_, ratings) = part_re
-                .captures(part)
-                .expect("Unable to parse part")
-                .extract();
-            // Hey it spells x-mas!
-            let [x, m, a, s] = ratings.map(|r| r.parse::<usize>().unwrap());
+    for part in sections
+        .next()
+        .iter()
+        .flat_map(|section| section.split('\n'))
+    {
+        let (_, ratings) = part_re
+            .captures(part)
+            .expect("Unable to parse part")
+            .extract();
+        // Hey it spells x-mas!
+        let [x, m, a, s] = ratings.map(|r| r.parse::<usize>().unwrap());
 
-            parts.push([x, m, a, s]);
-        } else {
-            break;
-        }
+        parts.push([x, m, a, s]);
     }
 
     (workflows, parts)
@@ -121,7 +116,8 @@ fn sort_parts<'a>(
 }
 
 pub fn day_19_sum_accepted_part_ratings(input: &str) -> usize {
-    let (workflows, parts) = parse_input(input);
+    let input = Input::new(input);
+    let (workflows, parts) = parse_input(&input);
 
     let (accepted, _) = sort_parts(&workflows, &parts);
 
@@ -136,7 +132,8 @@ pub fn day_19_sum_accepted_part_ratings(input: &str) -> usize {
 }
 
 pub fn input_has_cycle(input: &str) -> bool {
-    let (workflows, _) = parse_input(input);
+    let input = Input::new(input);
+    let (workflows, _) = parse_input(&input);
 
     let mut simplified_workflows = HashMap::new();
 
@@ -252,7 +249,8 @@ fn find_valid_space(conditions: [[usize; 2]; 4]) -> usize {
 }
 
 pub fn day_19_number_of_combinations_of_accepted_ratings(input: &str) -> usize {
-    let (workflows, _) = parse_input(input);
+    let input = Input::new(input);
+    let (workflows, _) = parse_input(&input);
 
     let accepted_conditions: Vec<[[usize; 2]; 4]> = find_all_cases(&workflows)
         .into_iter()
@@ -1,109 +1,175 @@
 use wasm_bindgen::prelude::*;
 
-use regex::Regex;
+use std::collections::BTreeMap;
+use std::fmt;
 
-/// Determines if game is valid given the game and the dice loaded into the bag
+use crate::input::Input;
+use crate::streaming::LineSolver;
+
+/// Number of cubes of each colour, with any colour not listed having none
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CubeSet {
+    pub cubes: BTreeMap<String, usize>,
+}
+
+impl CubeSet {
+    /// Builds a set from colours and counts, e.g. `CubeSet::new(&[("red", 12), ("green", 13), ("blue", 14)])`
+    pub fn new(cubes: &[(&str, usize)]) -> CubeSet {
+        let mut set = CubeSet::default();
+        for (colour, n) in cubes {
+            *set.cubes.entry(colour.to_string()).or_insert(0) += n;
+        }
+        set
+    }
+
+    pub fn get(&self, colour: &str) -> usize {
+        self.cubes.get(colour).copied().unwrap_or(0)
+    }
+
+    /// Determines if there are at least as many cubes of every colour as in `other`
+    pub fn contains(&self, other: &CubeSet) -> bool {
+        other.cubes.iter().all(|(colour, n)| self.get(colour) >= *n)
+    }
+
+    /// Smallest set containing both sets, i.e. the most of each colour
+    pub fn union(&self, other: &CubeSet) -> CubeSet {
+        let mut set = self.clone();
+        for (colour, n) in &other.cubes {
+            let count = set.cubes.entry(colour.clone()).or_insert(0);
+            *count = (*count).max(*n);
+        }
+        set
+    }
+
+    /// Product of the number of cubes of each of the colours, so 0 if any of them are missing
+    pub fn power(&self, colours: &[&str]) -> usize {
+        colours.iter().map(|colour| self.get(colour)).product()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum GameError {
+    /// A line which is not `Game <id>: <n> <colour>, ...; ...`
+    InvalidGame(String),
+    UnknownGame(usize),
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameError::InvalidGame(game) => write!(f, "Invalid game {}", game),
+            GameError::UnknownGame(id) => write!(f, "Unknown game {}", id),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Game {
+    pub id: usize,
+    /// Sets of cubes pulled out of the bag, in order
+    pub draws: Vec<CubeSet>,
+}
+
+impl Game {
+    /// Parses a game, e.g. `Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green`
+    pub fn parse(game: &str) -> Result<Game, GameError> {
+        let invalid = || GameError::InvalidGame(game.to_string());
+
+        let (id, draws) = game
+            .strip_prefix("Game ")
+            .and_then(|game| game.split_once(':'))
+            .ok_or_else(invalid)?;
+        let id = id.trim().parse::<usize>().map_err(|_| invalid())?;
+
+        let draws = draws
+            .split(';')
+            .map(|draw| {
+                let mut set = CubeSet::default();
+                for cubes in draw.split(',') {
+                    let mut cubes = cubes.split_whitespace();
+                    match (cubes.next(), cubes.next(), cubes.next()) {
+                        (Some(n), Some(colour), None) => {
+                            let n = n.parse::<usize>().map_err(|_| invalid())?;
+                            *set.cubes.entry(colour.to_string()).or_insert(0) += n;
+                        }
+                        _ => return Err(invalid()),
+                    }
+                }
+                Ok(set)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Game { id, draws })
+    }
+
+    /// Determines if every draw in the game could have come from the bag
+    pub fn is_possible_with(&self, bag: &CubeSet) -> bool {
+        self.draws.iter().all(|draw| bag.contains(draw))
+    }
+
+    /// Fewest cubes of each colour the bag could have held
+    pub fn minimal_bag(&self) -> CubeSet {
+        self.draws
+            .iter()
+            .fold(CubeSet::default(), |bag, draw| bag.union(draw))
+    }
+}
+
+/// Parses one game per line
+pub fn parse_games(games: &str) -> Result<Vec<Game>, GameError> {
+    Input::new(games).lines().map(Game::parse).collect()
+}
+
+/// Determines the per-colour lower bounds on the bags which make every one of the chosen games possible.
+/// A bag makes them all possible exactly when it contains the returned set.
 /// # Arguments
 ///
-/// * `game` String containing the game ID and sets of cubes oulled out in the game
-/// * `dice_loaded` Array of values of the dice loaded into the bag in the order [red, green, blue]
+/// * `games` Every game
+/// * `ids` IDs of the chosen games
 ///
 /// # Returns
-/// Array containing the game ID and if the game is valid
-fn game_is_valid(game: &str, dice_loaded: &[usize]) -> (usize, bool) {
-    let game_re = Regex::new(r"^Game (\d+): (.*)$").unwrap();
-    let caps = game_re.captures(game).unwrap();
-
-    let id = caps.get(1).unwrap().as_str().parse::<usize>().unwrap();
-
-    let sets = caps.get(2).unwrap().as_str().split(";");
-    let cube_res = [
-        Regex::new(r"(\d+) red").unwrap(),
-        Regex::new(r"(\d+) green").unwrap(),
-        Regex::new(r"(\d+) blue").unwrap(),
-    ];
-    for set in sets {
-        for (i, re) in cube_res.iter().enumerate() {
-            let caps = re.captures(set);
-            if caps.is_none() {
-                continue;
-            }
-            let n = caps
-                .unwrap()
-                .get(1)
-                .unwrap()
-                .as_str()
-                .parse::<usize>()
-                .unwrap();
-            if n > dice_loaded[i] {
-                return (id, false);
-            }
-        }
-    }
-
-    (id, true)
+/// Fewest cubes of each colour the bag must hold
+pub fn bag_lower_bounds(games: &[Game], ids: &[usize]) -> Result<CubeSet, GameError> {
+    ids.iter().try_fold(CubeSet::default(), |bag, id| {
+        let game = games
+            .iter()
+            .find(|game| game.id == *id)
+            .ok_or(GameError::UnknownGame(*id))?;
+        Ok(bag.union(&game.minimal_bag()))
+    })
 }
 
-/// Determines the sum of valid game ids given information about each game
-/// The bag was loaded with only 12 red cubes, 13 green cubes, and 14 blue cubes
+/// Determines the sum of the ids of the games possible with the bag
 /// # Arguments
 ///
 /// * `games` String containing information about each game
+/// * `bag` Cubes loaded into the bag
 ///
 /// # Returns
 /// Sum of possible game ids
-#[wasm_bindgen]
-pub fn day_2_get_sum_of_possible_game_ids(games: &str) -> usize {
-    let mut ans = 0;
-
-    let loaded_dice = [12, 13, 14];
-    for game in games.split("\n") {
-        let (id, valid) = game_is_valid(game.trim(), &loaded_dice);
-        if valid {
-            ans = ans + id;
-        }
-    }
-    ans
+pub fn day_2_get_sum_of_possible_game_ids_with_bag(
+    games: &str,
+    bag: &CubeSet,
+) -> Result<usize, GameError> {
+    Ok(parse_games(games)?
+        .iter()
+        .filter(|game| game.is_possible_with(bag))
+        .map(|game| game.id)
+        .sum())
 }
 
-/// Returns the product of the minimum number of each colour of cube required for each game.
+/// Determines the sum of valid game ids given information about each game
+/// The bag was loaded with only 12 red cubes, 13 green cubes, and 14 blue cubes
 /// # Arguments
 ///
-/// * `game` String containing the game ID and sets of cubes oulled out in the game
+/// * `games` String containing information about each game
 ///
 /// # Returns
-/// Product of the minimum number of each colour of cube required
-fn minimum_power(game: &str) -> usize {
-    let game_re = Regex::new(r"^.*:(.*)$").unwrap();
-    let caps = game_re.captures(game).unwrap();
-
-    let sets = caps.get(1).unwrap().as_str().split(";");
-    let cube_res = [
-        Regex::new(r"(\d+) red").unwrap(),
-        Regex::new(r"(\d+) green").unwrap(),
-        Regex::new(r"(\d+) blue").unwrap(),
-    ];
-    let mut min_cubes = [0, 0, 0];
-    for set in sets {
-        for (i, re) in cube_res.iter().enumerate() {
-            let caps = re.captures(set);
-            if caps.is_none() {
-                continue;
-            }
-            let n = caps
-                .unwrap()
-                .get(1)
-                .unwrap()
-                .as_str()
-                .parse::<usize>()
-                .unwrap();
-            if n > min_cubes[i] {
-                min_cubes[i] = n
-            }
-        }
-    }
-
-    min_cubes[0] * min_cubes[1] * min_cubes[2]
+/// Sum of possible game ids
+#[wasm_bindgen]
+pub fn day_2_get_sum_of_possible_game_ids(games: &str) -> usize {
+    let bag = CubeSet::new(&[("red", 12), ("green", 13), ("blue", 14)]);
+    day_2_get_sum_of_possible_game_ids_with_bag(games, &bag).unwrap()
 }
 
 /// Determines the sum of the power of minimum set of cubes that must have been present for each game given information about each game
@@ -115,27 +181,94 @@ fn minimum_power(game: &str) -> usize {
 /// Sum of the power of minimum set of cubes that must have been present for each game
 #[wasm_bindgen]
 pub fn day_2_get_sum_of_minimum_power(games: &str) -> usize {
-    let mut ans = 0;
+    parse_games(games)
+        .unwrap()
+        .iter()
+        .map(|game| game.minimal_bag().power(&["red", "green", "blue"]))
+        .sum()
+}
 
-    for game in games.split("\n") {
-        ans = ans + minimum_power(game.trim());
+/// Running sum of the ids of the games possible with the bag, for reading games a line at a time
+#[derive(Debug)]
+pub struct PossibleGameIds {
+    bag: CubeSet,
+    total: usize,
+}
+
+impl PossibleGameIds {
+    pub fn new(bag: CubeSet) -> PossibleGameIds {
+        PossibleGameIds { bag, total: 0 }
+    }
+}
+
+impl LineSolver for PossibleGameIds {
+    type Total = usize;
+    type Error = GameError;
+
+    fn feed(&mut self, line: &str) -> Result<(), GameError> {
+        let game = Game::parse(line)?;
+        if game.is_possible_with(&self.bag) {
+            self.total += game.id;
+        }
+        Ok(())
+    }
+
+    fn total(&self) -> usize {
+        self.total
+    }
+}
+
+/// Running sum of the power of the minimal bag for each game, for reading games a line at a time
+#[derive(Debug)]
+pub struct MinimumPowerSum {
+    colours: Vec<String>,
+    total: usize,
+}
+
+impl MinimumPowerSum {
+    pub fn new(colours: &[&str]) -> MinimumPowerSum {
+        MinimumPowerSum {
+            colours: colours.iter().map(|colour| colour.to_string()).collect(),
+            total: 0,
+        }
+    }
+}
+
+impl LineSolver for MinimumPowerSum {
+    type Total = usize;
+    type Error = GameError;
+
+    fn feed(&mut self, line: &str) -> Result<(), GameError> {
+        let colours = self
+            .colours
+            .iter()
+            .map(|colour| colour.as_str())
+            .collect::<Vec<_>>();
+        self.total += Game::parse(line)?.minimal_bag().power(&colours);
+        Ok(())
+    }
+
+    fn total(&self) -> usize {
+        self.total
     }
-    ans
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::streaming::{final_total, running_totals};
+
     macro_rules! generate_game_is_valid_tests {
         ($($name:ident: $value:expr,)*) => {
         $(
             #[test]
             fn $name() {
-                let ((game, dice_loaded), (expected_id, expected_validity)) = $value;
-                let (id, valid) = game_is_valid(game, &dice_loaded);
-                assert_eq!(expected_id, id);
-                assert_eq!(expected_validity, valid);
+                let ((game, [red, green, blue]), (expected_id, expected_validity)) = $value;
+                let game = Game::parse(game).unwrap();
+                let bag = CubeSet::new(&[("red", red), ("green", green), ("blue", blue)]);
+                assert_eq!(expected_id, game.id);
+                assert_eq!(expected_validity, game.is_possible_with(&bag));
             }
         )*
         }
@@ -169,7 +302,8 @@ mod tests {
             #[test]
             fn $name() {
                 let (game, expected) = $value;
-                assert_eq!(expected, minimum_power(game));
+                let game = Game::parse(game).unwrap();
+                assert_eq!(expected, game.minimal_bag().power(&["red", "green", "blue"]));
             }
         )*
         }
@@ -183,6 +317,89 @@ mod tests {
         test_minimum_power_5: ("Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green", 36),
     }
 
+    #[test]
+    fn test_parse_game() {
+        assert_eq!(
+            Ok(Game {
+                id: 7,
+                draws: vec![
+                    CubeSet::new(&[("mauve", 2), ("red", 1)]),
+                    CubeSet::new(&[("teal", 10)])
+                ]
+            }),
+            Game::parse("Game 7: 2 mauve, 1 red; 10 teal")
+        );
+        for game in [
+            "Game x: 1 red",
+            "7: 1 red",
+            "Game 7: red",
+            "Game 7: 1 red 2",
+        ] {
+            assert_eq!(
+                Err(GameError::InvalidGame(game.to_string())),
+                Game::parse(game)
+            );
+        }
+    }
+
+    #[test]
+    fn test_bag_queries() {
+        let games = parse_games(
+            r#"Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+            Game 2: 2 mauve; 1 mauve, 3 red
+            Game 3: 8 green, 6 blue, 20 red"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            CubeSet::new(&[("red", 3), ("mauve", 2)]),
+            games[1].minimal_bag()
+        );
+        assert_eq!(6, games[1].minimal_bag().power(&["red", "mauve"]));
+        assert_eq!(0, games[1].minimal_bag().power(&["red", "green", "blue"]));
+
+        let bag = CubeSet::new(&[("red", 5), ("green", 10), ("blue", 10)]);
+        assert!(games[0].is_possible_with(&bag));
+        assert!(!games[1].is_possible_with(&bag));
+        assert!(!games[2].is_possible_with(&bag));
+
+        let bounds = bag_lower_bounds(&games, &[1, 2]).unwrap();
+        assert_eq!(
+            CubeSet::new(&[("red", 4), ("green", 2), ("blue", 6), ("mauve", 2)]),
+            bounds
+        );
+        assert!(games[0].is_possible_with(&bounds) && games[1].is_possible_with(&bounds));
+        assert_eq!(Ok(CubeSet::default()), bag_lower_bounds(&games, &[]));
+        assert_eq!(
+            Err(GameError::UnknownGame(4)),
+            bag_lower_bounds(&games, &[1, 4])
+        );
+    }
+
+    #[test]
+    fn test_streaming_games() {
+        let games = r#"Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+        Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+        Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+        "#;
+
+        let bag = CubeSet::new(&[("red", 12), ("green", 13), ("blue", 14)]);
+        assert_eq!(
+            vec![1, 3, 3],
+            running_totals(games.as_bytes(), PossibleGameIds::new(bag))
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        );
+        assert_eq!(
+            Some(48 + 12 + 1560),
+            final_total(
+                games.as_bytes(),
+                MinimumPowerSum::new(&["red", "green", "blue"])
+            )
+            .unwrap()
+        );
+    }
+
     #[test]
     fn test_day_2_get_sum_of_minimum_power() {
         assert_eq!(
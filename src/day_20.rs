@@ -1,5 +1,7 @@
 use std::collections::{HashMap, VecDeque};
 
+use crate::input::Input;
+
 const HIGH: bool = true;
 const LOW: bool = false;
 #[allow(dead_code)]
@@ -77,7 +79,8 @@ fn parse_module_configuration(
     let mut input_configuration: HashMap<String, Vec<String>> = HashMap::new();
     let mut conjunctions: Vec<String> = Vec::new();
 
-    for line in input.split("\n").map(|line| line.trim()) {
+    let input = Input::new(input);
+    for line in input.lines() {
         let mut parts = line.split("->").map(|part| part.trim());
 
         let current_module = parts.next().unwrap();
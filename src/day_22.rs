@@ -1,5 +1,7 @@
 use std::cmp::min;
 
+use crate::input::Input;
+
 #[derive(Debug)]
 struct Position {
     x: usize,
@@ -16,7 +18,8 @@ struct Block {
 fn parse_input(input: &str) -> Vec<Block> {
     let mut blocks: Vec<Block> = Vec::new();
 
-    for line in input.split("\n").map(|line| line.trim()) {
+    let input = Input::new(input);
+    for line in input.lines() {
         let positions = line
             .split('~')
             .map(|position| {
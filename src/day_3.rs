@@ -1,153 +1,250 @@
 use wasm_bindgen::prelude::*;
 
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 
-fn load_schematic(schematic: &str) -> Vec<Vec<char>> {
-    schematic
-        .split("\n")
-        .map(|line| line.trim().chars().collect())
-        .collect()
+use crate::input::Input;
+
+#[derive(Debug, PartialEq)]
+pub struct Number {
+    pub value: usize,
+    pub row: usize,
+    // First and last column of the digits
+    pub span: (usize, usize),
 }
 
-#[wasm_bindgen]
-pub fn day_3_get_sum_of_part_numbers(schematic: &str) -> usize {
-    let mut ans = 0;
-    let indexable_schematic = load_schematic(schematic);
-    let n = indexable_schematic.len();
-    let m = indexable_schematic[0].len();
-    for (i, line) in indexable_schematic.iter().enumerate() {
-        let mut s = 0;
-        let mut number_str: String = "".to_string();
-        for (j, c) in line.iter().enumerate() {
-            if c.is_digit(10) {
-                if number_str == "" {
-                    s = j;
-                }
-                number_str = format!("{}{}", number_str, c);
-
-                if (j == m - 1) || !indexable_schematic[i][j + 1].is_digit(10) {
-                    let number = number_str.parse::<usize>().unwrap();
-                    let e = j;
-
-                    let u = if i >= 1 { i - 1 } else { i };
-                    let d = if i < n - 1 { i + 1 } else { i };
-                    let l = if s >= 1 { s - 1 } else { s };
-                    let r = if e < m - 1 { e + 1 } else { e };
-
-                    let include = 'block: {
-                        for y in u..=d {
-                            if y == i {
-                                for x in [l, r] {
-                                    let c = indexable_schematic[y][x];
-                                    if !c.is_digit(10) && c != '.' {
-                                        break 'block true;
-                                    }
-                                }
-                            } else {
-                                for x in l..=r {
-                                    let c = indexable_schematic[y][x];
-                                    if !c.is_digit(10) && c != '.' {
-                                        break 'block true;
-                                    }
-                                }
-                            }
-                        }
-                        false
-                    };
+#[derive(Debug, PartialEq)]
+pub struct Symbol {
+    pub glyph: char,
+    pub position: (usize, usize),
+}
 
-                    if include {
-                        ans = ans + number;
-                    }
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AdjacentCount {
+    Exactly(usize),
+    AtLeast(usize),
+    AtMost(usize),
+}
 
-                    number_str = "".to_string();
-                }
-            }
+impl AdjacentCount {
+    fn matches(&self, count: usize) -> bool {
+        match *self {
+            AdjacentCount::Exactly(n) => count == n,
+            AdjacentCount::AtLeast(n) => count >= n,
+            AdjacentCount::AtMost(n) => count <= n,
         }
     }
+}
 
-    ans
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregation {
+    Product,
+    Sum,
+    Max,
 }
 
-#[derive(Debug)]
-struct Part {
-    start: usize,
-    end: usize,
-    number: usize,
-}
-
-fn extract_parts(schematic: &str) -> HashMap<usize, Vec<Part>> {
-    let mut parts: HashMap<usize, Vec<Part>> = HashMap::new();
-    for (i, line) in schematic.split("\n").map(|line| line.trim()).enumerate() {
-        let mut parts_on_line = Vec::new();
-        let mut s = 0;
-        let mut number_str: String = "".to_string();
-        let chars: Vec<char> = line.chars().collect();
-        let m = chars.len();
-        for j in 0..m {
-            let c = chars[j];
-            if c.is_digit(10) {
-                if number_str == "" {
-                    s = j;
-                }
-                number_str = format!("{}{}", number_str, c);
-
-                if (j == m - 1) || !chars[j + 1].is_digit(10) {
-                    parts_on_line.push(Part {
-                        start: s,
-                        end: j,
-                        number: number_str.parse::<usize>().unwrap(),
-                    });
-                    number_str = "".to_string();
-                }
-            }
+impl Aggregation {
+    // A gear touching no parts is worth 1 with Product, and 0 otherwise
+    fn apply(&self, values: &[usize]) -> usize {
+        match self {
+            Aggregation::Product => values.iter().product(),
+            Aggregation::Sum => values.iter().sum(),
+            Aggregation::Max => values.iter().copied().max().unwrap_or(0),
         }
-        if parts_on_line.len() != 0 {
-            parts.insert(i, parts_on_line);
+    }
+}
+
+// Which symbols count as gears and what each one is worth
+#[derive(Debug, Clone)]
+pub struct GearRule {
+    pub glyphs: Vec<char>,
+    // How many part numbers a symbol must touch to be a gear
+    pub count: AdjacentCount,
+    pub aggregation: Aggregation,
+}
+
+impl GearRule {
+    // Part 2, a * touching exactly two part numbers is worth their product
+    pub fn gear_ratio() -> GearRule {
+        GearRule {
+            glyphs: vec!['*'],
+            count: AdjacentCount::Exactly(2),
+            aggregation: Aggregation::Product,
         }
     }
+}
 
-    parts
+#[derive(Debug, PartialEq)]
+pub struct Gear {
+    pub glyph: char,
+    pub position: (usize, usize),
+    pub part_numbers: Vec<usize>,
+    pub value: usize,
 }
 
-#[wasm_bindgen]
-pub fn day_3_get_sum_of_gear_ratios(schematic: &str) -> usize {
-    let mut ans = 0;
+#[derive(Debug, PartialEq)]
+pub struct GearReport {
+    pub total: usize,
+    // In reading order
+    pub gears: Vec<Gear>,
+}
 
-    let parts = extract_parts(schematic);
+// Every number and symbol in the schematic, in reading order, and which of them touch, including diagonally
+#[derive(Debug)]
+pub struct Schematic {
+    pub numbers: Vec<Number>,
+    pub symbols: Vec<Symbol>,
+    // Indices into `symbols` for each number, and into `numbers` for each symbol
+    number_symbols: Vec<Vec<usize>>,
+    symbol_numbers: Vec<Vec<usize>>,
+}
 
-    for (i, line) in schematic.split("\n").map(|line| line.trim()).enumerate() {
-        for (j, c) in line.chars().enumerate() {
-            if c == '*' {
-                let mut adjacent_parts = HashSet::new();
+impl Schematic {
+    // Anything that is not a digit or a '.' is a symbol
+    pub fn parse(schematic: &str) -> Schematic {
+        let mut numbers = Vec::new();
+        let mut symbols = Vec::new();
 
-                for y in i - 1..=i + 1 {
-                    for x in j - 1..=j + 1 {
-                        if y == i && x == j {
-                            continue;
-                        }
-                        let parts_on_line = parts.get(&y);
-                        if parts_on_line.is_none() {
-                            continue;
-                        }
-                        for part in parts_on_line.unwrap() {
-                            if x >= part.start && x <= part.end {
-                                adjacent_parts.insert(part.number);
-                            }
+        let schematic = Input::new(schematic);
+        for (i, line) in schematic.lines().enumerate() {
+            let mut number: Option<Number> = None;
+            for (j, c) in line.chars().enumerate() {
+                match c.to_digit(10) {
+                    Some(digit) => {
+                        let number = number.get_or_insert(Number {
+                            value: 0,
+                            row: i,
+                            span: (j, j),
+                        });
+                        number.value = number.value * 10 + digit as usize;
+                        number.span.1 = j;
+                    }
+                    None => {
+                        numbers.extend(number.take());
+                        if c != '.' {
+                            symbols.push(Symbol {
+                                glyph: c,
+                                position: (i, j),
+                            });
                         }
                     }
                 }
-                if adjacent_parts.len() == 2 {
-                    let mut a = 1;
-                    for part in adjacent_parts {
-                        a = a * part;
+            }
+            numbers.extend(number);
+        }
+
+        let symbol_at: HashMap<(usize, usize), usize> = symbols
+            .iter()
+            .enumerate()
+            .map(|(k, symbol)| (symbol.position, k))
+            .collect();
+
+        let mut number_symbols = vec![Vec::new(); numbers.len()];
+        let mut symbol_numbers = vec![Vec::new(); symbols.len()];
+        for (k, number) in numbers.iter().enumerate() {
+            let (start, end) = number.span;
+            for y in number.row.saturating_sub(1)..=number.row + 1 {
+                for x in start.saturating_sub(1)..=end + 1 {
+                    if let Some(&l) = symbol_at.get(&(y, x)) {
+                        number_symbols[k].push(l);
+                        symbol_numbers[l].push(k);
                     }
-                    ans = ans + a;
                 }
             }
         }
+        // Numbers were visited in reading order, but their symbols row by row
+        for symbols in number_symbols.iter_mut() {
+            symbols.sort_unstable();
+        }
+
+        Schematic {
+            numbers,
+            symbols,
+            number_symbols,
+            symbol_numbers,
+        }
+    }
+
+    // Indices of the symbols touching a number
+    pub fn symbols_adjacent_to(&self, number: usize) -> &[usize] {
+        &self.number_symbols[number]
     }
 
-    ans
+    // Indices of the numbers touching a symbol
+    pub fn numbers_adjacent_to(&self, symbol: usize) -> &[usize] {
+        &self.symbol_numbers[symbol]
+    }
+
+    // Numbers touching at least one symbol
+    pub fn part_numbers(&self) -> impl Iterator<Item = &Number> {
+        self.numbers
+            .iter()
+            .zip(&self.number_symbols)
+            .filter(|(_, symbols)| !symbols.is_empty())
+            .map(|(number, _)| number)
+    }
+
+    // Numbers touching at least one symbol with the glyph
+    pub fn part_numbers_touching(&self, glyph: char) -> impl Iterator<Item = &Number> + '_ {
+        self.numbers
+            .iter()
+            .zip(&self.number_symbols)
+            .filter(move |(_, symbols)| symbols.iter().any(|l| self.symbols[*l].glyph == glyph))
+            .map(|(number, _)| number)
+    }
+
+    // Symbols touching no numbers
+    pub fn isolated_symbols(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols
+            .iter()
+            .zip(&self.symbol_numbers)
+            .filter(|(_, numbers)| numbers.is_empty())
+            .map(|(symbol, _)| symbol)
+    }
+
+    pub fn gears(&self, rule: &GearRule) -> GearReport {
+        let gears = self
+            .symbols
+            .iter()
+            .zip(&self.symbol_numbers)
+            .filter(|(symbol, numbers)| {
+                rule.glyphs.contains(&symbol.glyph) && rule.count.matches(numbers.len())
+            })
+            .map(|(symbol, numbers)| {
+                let part_numbers = numbers
+                    .iter()
+                    .map(|k| self.numbers[*k].value)
+                    .collect::<Vec<_>>();
+                Gear {
+                    glyph: symbol.glyph,
+                    position: symbol.position,
+                    value: rule.aggregation.apply(&part_numbers),
+                    part_numbers,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        GearReport {
+            total: gears.iter().map(|gear| gear.value).sum(),
+            gears,
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn day_3_get_sum_of_part_numbers(schematic: &str) -> usize {
+    Schematic::parse(schematic)
+        .part_numbers()
+        .map(|number| number.value)
+        .sum()
+}
+
+pub fn day_3_apply_gear_rule(schematic: &str, rule: &GearRule) -> GearReport {
+    Schematic::parse(schematic).gears(rule)
+}
+
+#[wasm_bindgen]
+pub fn day_3_get_sum_of_gear_ratios(schematic: &str) -> usize {
+    day_3_apply_gear_rule(schematic, &GearRule::gear_ratio()).total
 }
 
 #[cfg(test)]
@@ -165,6 +262,132 @@ mod tests {
     ...$.*....
     .664.598.."#;
 
+    #[test]
+    fn test_parse_schematic() {
+        let schematic = Schematic::parse(EXAMPLE_SCHEMATIC);
+
+        assert_eq!(10, schematic.numbers.len());
+        assert_eq!(
+            Number {
+                value: 467,
+                row: 0,
+                span: (0, 2)
+            },
+            schematic.numbers[0]
+        );
+        assert_eq!(
+            Number {
+                value: 58,
+                row: 5,
+                span: (7, 8)
+            },
+            schematic.numbers[5]
+        );
+        assert_eq!(
+            vec!['*', '#', '*', '+', '$', '*'],
+            schematic
+                .symbols
+                .iter()
+                .map(|symbol| symbol.glyph)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!((8, 3), schematic.symbols[4].position);
+
+        // 467 and 35 touch the first *
+        assert_eq!(&[0], schematic.symbols_adjacent_to(0));
+        assert_eq!(&[0, 2], schematic.numbers_adjacent_to(0));
+        // 114 and 58 touch nothing
+        assert!(schematic.symbols_adjacent_to(1).is_empty());
+        assert!(schematic.symbols_adjacent_to(5).is_empty());
+    }
+
+    #[test]
+    fn test_schematic_queries() {
+        let schematic = Schematic::parse(EXAMPLE_SCHEMATIC);
+
+        assert_eq!(
+            vec![664],
+            schematic
+                .part_numbers_touching('$')
+                .map(|number| number.value)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(None, schematic.isolated_symbols().next());
+
+        let schematic = Schematic::parse("12.%\n..#.\n3..4");
+        assert_eq!(
+            vec![12, 4],
+            schematic
+                .part_numbers()
+                .map(|number| number.value)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![&Symbol {
+                glyph: '%',
+                position: (0, 3)
+            }],
+            schematic.isolated_symbols().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_day_3_apply_gear_rule() {
+        let report = day_3_apply_gear_rule(EXAMPLE_SCHEMATIC, &GearRule::gear_ratio());
+        assert_eq!(
+            vec![
+                Gear {
+                    glyph: '*',
+                    position: (1, 3),
+                    part_numbers: vec![467, 35],
+                    value: 16345
+                },
+                Gear {
+                    glyph: '*',
+                    position: (8, 5),
+                    part_numbers: vec![755, 598],
+                    value: 451490
+                },
+            ],
+            report.gears
+        );
+
+        // Any symbol with a single part, keeping the largest
+        let rule = GearRule {
+            glyphs: vec!['*', '#', '+', '$'],
+            count: AdjacentCount::AtMost(1),
+            aggregation: Aggregation::Max,
+        };
+        let report = day_3_apply_gear_rule(EXAMPLE_SCHEMATIC, &rule);
+        assert_eq!(
+            vec![(3, 6), (4, 3), (5, 5), (8, 3)],
+            report
+                .gears
+                .iter()
+                .map(|gear| gear.position)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(633 + 617 + 592 + 664, report.total);
+
+        // Three way gears, summed
+        let schematic = "1.2\n.*.\n3.4\n*..";
+        let rule = GearRule {
+            glyphs: vec!['*'],
+            count: AdjacentCount::AtLeast(3),
+            aggregation: Aggregation::Sum,
+        };
+        let report = day_3_apply_gear_rule(schematic, &rule);
+        assert_eq!(1, report.gears.len());
+        assert_eq!(vec![1, 2, 3, 4], report.gears[0].part_numbers);
+        assert_eq!(10, report.total);
+        let rule = GearRule {
+            aggregation: Aggregation::Product,
+            count: AdjacentCount::Exactly(1),
+            ..rule
+        };
+        assert_eq!(3, day_3_apply_gear_rule(schematic, &rule).total);
+    }
+
     #[test]
     fn test_day_3_get_sum_of_part_numbers() {
         assert_eq!(4361, day_3_get_sum_of_part_numbers(EXAMPLE_SCHEMATIC));
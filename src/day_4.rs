@@ -2,15 +2,19 @@ use wasm_bindgen::prelude::*;
 
 use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
+use std::fmt;
 use std::iter::FromIterator;
 
-use regex::Regex;
+use crate::input::Input;
+use crate::streaming::LineSolver;
 
 fn identify_card_point_value(card: &str) -> usize {
-    let (_, winning_numbers, card_numbers) = parse_card(card);
+    let (_, winning_numbers, card_numbers) = parse_card(card).unwrap();
 
-    let n_matches = get_number_of_matches(winning_numbers, card_numbers);
+    points(get_number_of_matches(winning_numbers, card_numbers))
+}
 
+fn points(n_matches: usize) -> usize {
     match n_matches {
         0 => 0,
         n => 2_usize.pow((n - 1).try_into().unwrap()),
@@ -20,19 +24,17 @@ fn identify_card_point_value(card: &str) -> usize {
 #[wasm_bindgen]
 pub fn day_4_total_scratchcard_points(cards: &str) -> usize {
     let mut ans = 0;
-    for card in cards.split("\n").map(|line| line.trim()) {
+    for card in Input::new(cards).lines() {
         ans = ans + identify_card_point_value(card);
     }
     ans
 }
 
-fn parse_card(card: &str) -> (usize, HashSet<&str>, HashSet<&str>) {
-    let re = Regex::new(r"^Card\s+(\d+):(.*)\|(.*)$").unwrap();
-    let caps = re.captures(card).unwrap();
-
-    let n = caps.get(1).unwrap().as_str().parse::<usize>().unwrap();
-    let l = caps.get(2).unwrap().as_str().trim();
-    let r = caps.get(3).unwrap().as_str().trim();
+// None unless the card is `Card <n>: <winning numbers> | <numbers>`
+fn parse_card(card: &str) -> Option<(usize, HashSet<&str>, HashSet<&str>)> {
+    let (n, numbers) = card.strip_prefix("Card")?.split_once(':')?;
+    let n = n.trim().parse::<usize>().ok()?;
+    let (l, r) = numbers.split_once('|')?;
 
     let winning_numbers = HashSet::<&str>::from_iter(
         l.split_whitespace()
@@ -45,7 +47,26 @@ fn parse_card(card: &str) -> (usize, HashSet<&str>, HashSet<&str>) {
             .collect::<Vec<&str>>(),
     );
 
-    (n, winning_numbers, card_numbers)
+    Some((n, winning_numbers, card_numbers))
+}
+
+#[derive(Debug, PartialEq)]
+pub struct InvalidCardError {
+    pub card: String,
+}
+
+impl fmt::Display for InvalidCardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid card {}", self.card)
+    }
+}
+
+fn count_matches(card: &str) -> Result<(usize, usize), InvalidCardError> {
+    let (i, winning_numbers, card_numbers) = parse_card(card).ok_or_else(|| InvalidCardError {
+        card: card.to_string(),
+    })?;
+
+    Ok((i, get_number_of_matches(winning_numbers, card_numbers)))
 }
 
 fn get_number_of_matches(winning_numbers: HashSet<&str>, card_numbers: HashSet<&str>) -> usize {
@@ -55,41 +76,190 @@ fn get_number_of_matches(winning_numbers: HashSet<&str>, card_numbers: HashSet<&
         .len()
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Addressing {
+    // Cards won are found by their card number, so missing card numbers are skipped over
+    CardNumber,
+    // Cards won are found by their position in the table
+    Position,
+}
+
+// Which cards a card with n matches wins a copy of: the cards at offset, offset + stride, ... for n cards
+#[derive(Debug, Clone)]
+pub struct PayoutRule {
+    pub offset: usize,
+    pub stride: usize,
+    pub addressing: Addressing,
+    // Most copies of any one card, with anything won beyond that lost
+    pub cap: Option<usize>,
+}
+
+impl PayoutRule {
+    // Part 2, a card with n matches wins the next n cards
+    pub fn standard() -> PayoutRule {
+        PayoutRule {
+            offset: 1,
+            stride: 1,
+            addressing: Addressing::CardNumber,
+            cap: None,
+        }
+    }
+}
+
+// The card number and final number of copies, including the original, of every card in the order they are listed.
+// Only cards later in the table can be won, so cards beyond the end or before the current one are ignored.
+pub fn day_4_simulate_cascade(
+    cards: &str,
+    rule: &PayoutRule,
+) -> Result<Vec<(usize, usize)>, InvalidCardError> {
+    let input = Input::new(cards);
+    let cards = input
+        .lines()
+        .map(count_matches)
+        .collect::<Result<Vec<_>, _>>()?;
+    let positions: HashMap<usize, usize> = cards
+        .iter()
+        .enumerate()
+        .map(|(p, (i, _))| (*i, p))
+        .collect();
+
+    let mut copies = vec![1_usize; cards.len()];
+    for (p, (i, n_matches)) in cards.iter().enumerate() {
+        for j in 0..*n_matches {
+            let distance = match j
+                .checked_mul(rule.stride)
+                .and_then(|d| d.checked_add(rule.offset))
+            {
+                Some(distance) => distance,
+                None => break,
+            };
+            let target = match rule.addressing {
+                Addressing::CardNumber => i
+                    .checked_add(distance)
+                    .and_then(|i| positions.get(&i).copied()),
+                Addressing::Position => p.checked_add(distance),
+            };
+
+            if let Some(q) = target.filter(|q| *q > p && *q < cards.len()) {
+                copies[q] = copies[q].saturating_add(copies[p]);
+                if let Some(cap) = rule.cap {
+                    copies[q] = copies[q].min(cap);
+                }
+            }
+        }
+    }
+
+    Ok(cards.iter().map(|(i, _)| *i).zip(copies).collect())
+}
+
 #[wasm_bindgen]
 pub fn day_4_get_final_number_of_cards(cards: &str) -> usize {
-    let mut ans = 0;
+    day_4_simulate_cascade(cards, &PayoutRule::standard())
+        .unwrap()
+        .iter()
+        .map(|(_, copies)| copies)
+        .sum()
+}
 
-    let mut n_cards: HashMap<usize, usize> = HashMap::new();
+// Running total of the points of cards read a line at a time
+#[derive(Debug, Default)]
+pub struct ScratchcardPoints {
+    total: usize,
+}
 
-    for card in cards.split("\n").map(|line| line.trim()) {
-        let (i, winning_numbers, card_numbers) = parse_card(card);
-        let n_matches = get_number_of_matches(winning_numbers, card_numbers);
+impl LineSolver for ScratchcardPoints {
+    type Total = usize;
+    type Error = InvalidCardError;
 
-        let mut n = 1;
+    fn feed(&mut self, line: &str) -> Result<(), InvalidCardError> {
+        let (_, n_matches) = count_matches(line)?;
+        self.total += points(n_matches);
+        Ok(())
+    }
 
-        if let Some(extra_cards) = n_cards.remove(&i) {
-            n = n + extra_cards;
+    fn total(&self) -> usize {
+        self.total
+    }
+}
+
+// Running total of the cards held, for cards read a line at a time. Only copies of cards yet to be read are kept, so
+// this matches `day_4_simulate_cascade` as long as card numbers are not repeated.
+#[derive(Debug)]
+pub struct ScratchcardCascade {
+    rule: PayoutRule,
+    // Copies, including the original, of cards won but not read yet, by card number or position
+    pending: HashMap<usize, usize>,
+    position: usize,
+    // Copies of the last card read
+    last: usize,
+    total: usize,
+}
+
+impl ScratchcardCascade {
+    pub fn new(rule: PayoutRule) -> ScratchcardCascade {
+        ScratchcardCascade {
+            rule,
+            pending: HashMap::new(),
+            position: 0,
+            last: 0,
+            total: 0,
         }
+    }
+
+    // Copies of the last card read, including the original
+    pub fn last_copies(&self) -> usize {
+        self.last
+    }
+}
+
+impl LineSolver for ScratchcardCascade {
+    type Total = usize;
+    type Error = InvalidCardError;
 
-        for j in 1..=n_matches {
-            dbg!(&j);
-            if let Some(extra_cards) = n_cards.get(&(i + j)) {
-                n_cards.insert(i + j, extra_cards + n);
-            } else {
-                n_cards.insert(i + j, n);
+    fn feed(&mut self, line: &str) -> Result<(), InvalidCardError> {
+        let (i, n_matches) = count_matches(line)?;
+        let key = match self.rule.addressing {
+            Addressing::CardNumber => i,
+            Addressing::Position => self.position,
+        };
+        self.position += 1;
+
+        let copies = self.pending.remove(&key).unwrap_or(1);
+        for j in 0..n_matches {
+            let target = j
+                .checked_mul(self.rule.stride)
+                .and_then(|d| d.checked_add(self.rule.offset))
+                .and_then(|d| key.checked_add(d));
+            let target = match target {
+                // A card can't win a copy of itself
+                Some(target) if target == key => continue,
+                Some(target) => target,
+                None => break,
+            };
+
+            let pending = self.pending.entry(target).or_insert(1);
+            *pending = pending.saturating_add(copies);
+            if let Some(cap) = self.rule.cap {
+                *pending = (*pending).min(cap);
             }
         }
 
-        ans = ans + n;
+        self.last = copies;
+        self.total = self.total.saturating_add(copies);
+        Ok(())
     }
 
-    ans
+    fn total(&self) -> usize {
+        self.total
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::streaming::{final_total, running_totals};
+
     const EXAMPLE: &str = r#"Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
     Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
     Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
@@ -121,6 +291,153 @@ mod tests {
     #[test]
     fn test_day_4_total_scratchcard_points() {
         assert_eq!(13, day_4_total_scratchcard_points(EXAMPLE));
+        // As saved by an editor on Windows
+        assert_eq!(
+            13,
+            day_4_total_scratchcard_points(&format!("{}\r\n", EXAMPLE.replace('\n', "\r\n")))
+        );
+    }
+
+    #[test]
+    fn test_day_4_simulate_cascade() {
+        assert_eq!(
+            vec![(1, 1), (2, 2), (3, 4), (4, 8), (5, 14), (6, 1)],
+            day_4_simulate_cascade(EXAMPLE, &PayoutRule::standard()).unwrap()
+        );
+
+        // Winning every other card
+        let rule = PayoutRule {
+            stride: 2,
+            ..PayoutRule::standard()
+        };
+        assert_eq!(
+            vec![(1, 1), (2, 2), (3, 3), (4, 5), (5, 8), (6, 5)],
+            day_4_simulate_cascade(EXAMPLE, &rule).unwrap()
+        );
+
+        // Winning cards two further on, with piles of at most 3
+        let rule = PayoutRule {
+            offset: 2,
+            cap: Some(3),
+            ..PayoutRule::standard()
+        };
+        assert_eq!(
+            vec![(1, 1), (2, 1), (3, 2), (4, 3), (5, 3), (6, 3)],
+            day_4_simulate_cascade(EXAMPLE, &rule).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_day_4_simulate_cascade_gaps() {
+        // Card 1 wins cards 2 to 5, which are beyond the end or missing
+        let cards = r#"Card 1: 1 2 3 4 | 1 2 3 4
+        Card 3: 5 | 5
+        Card 9: 1 | 2"#;
+        assert_eq!(
+            vec![(1, 1), (3, 2), (9, 1)],
+            day_4_simulate_cascade(cards, &PayoutRule::standard()).unwrap()
+        );
+        let rule = PayoutRule {
+            addressing: Addressing::Position,
+            ..PayoutRule::standard()
+        };
+        assert_eq!(
+            vec![(1, 1), (3, 2), (9, 4)],
+            day_4_simulate_cascade(cards, &rule).unwrap()
+        );
+
+        let rule = PayoutRule {
+            offset: usize::MAX,
+            stride: usize::MAX,
+            ..PayoutRule::standard()
+        };
+        assert_eq!(
+            vec![(1, 1), (3, 1), (9, 1)],
+            day_4_simulate_cascade(cards, &rule).unwrap()
+        );
+
+        assert_eq!(
+            Err(InvalidCardError {
+                card: "Card 2 1 | 1".to_string()
+            }),
+            day_4_simulate_cascade("Card 1: 1 | 1\nCard 2 1 | 1", &PayoutRule::standard())
+        );
+    }
+
+    #[test]
+    fn test_streaming_scratchcards() {
+        assert_eq!(
+            Some(13),
+            final_total(EXAMPLE.as_bytes(), ScratchcardPoints::default()).unwrap()
+        );
+        assert_eq!(
+            vec![1, 3, 7, 15, 29, 30],
+            running_totals(
+                EXAMPLE.as_bytes(),
+                ScratchcardCascade::new(PayoutRule::standard())
+            )
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+        );
+
+        // Same copies of each card as simulating the whole table
+        let cards = "Card 1: 1 2 3 4 | 1 2 3 4\nCard 3: 5 | 5\nCard 9: 1 | 2";
+        for rule in [
+            PayoutRule::standard(),
+            PayoutRule {
+                addressing: Addressing::Position,
+                ..PayoutRule::standard()
+            },
+            PayoutRule {
+                offset: 2,
+                cap: Some(3),
+                ..PayoutRule::standard()
+            },
+            PayoutRule {
+                offset: 0,
+                ..PayoutRule::standard()
+            },
+        ] {
+            for table in [EXAMPLE, cards] {
+                let mut totals =
+                    running_totals(table.as_bytes(), ScratchcardCascade::new(rule.clone()));
+                let mut copies = Vec::new();
+                while let Some(total) = totals.next() {
+                    total.unwrap();
+                    copies.push(totals.solver().last_copies());
+                }
+                assert_eq!(
+                    day_4_simulate_cascade(table, &rule)
+                        .unwrap()
+                        .iter()
+                        .map(|(_, copies)| *copies)
+                        .collect::<Vec<_>>(),
+                    copies
+                );
+            }
+        }
+
+        // The first card won is the card itself, which is skipped rather than ending the cascade
+        let rule = PayoutRule {
+            offset: 0,
+            ..PayoutRule::standard()
+        };
+        assert_eq!(
+            Some(
+                day_4_simulate_cascade(EXAMPLE, &rule)
+                    .unwrap()
+                    .iter()
+                    .map(|(_, copies)| copies)
+                    .sum()
+            ),
+            final_total(EXAMPLE.as_bytes(), ScratchcardCascade::new(rule)).unwrap()
+        );
+
+        assert!(final_total(
+            "Card 1: 1 | 1\nCard 2 1 | 1".as_bytes(),
+            ScratchcardPoints::default()
+        )
+        .is_err());
     }
 
     #[test]
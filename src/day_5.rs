@@ -1,99 +1,372 @@
 use wasm_bindgen::prelude::*;
 
-use std::collections::HashMap;
+use std::fmt;
 
 use regex::Regex;
 
-fn parse_almanac(
-    almanac: &str,
-) -> (
-    Vec<usize>,
-    HashMap<&str, Vec<(usize, usize, usize)>>,
-    HashMap<&str, &str>,
-) {
-    let mut almanac_iterator = almanac.split("\n").map(|line| line.trim());
-
-    // Consume first line which contains a listing of which seeds need to be planted
-    let seeds_line = almanac_iterator.next().unwrap();
-    let re = Regex::new(r"^seeds:(.*)$").unwrap();
-    let caps = re.captures(seeds_line).unwrap();
-    let seeds = caps
-        .get(1)
-        .unwrap()
-        .as_str()
-        .trim()
-        .split_whitespace()
-        .map(|x| x.trim().parse::<usize>().unwrap())
-        .collect();
-
-    // Skip new line separator
-    almanac_iterator.next();
-
-    let mut number_map: HashMap<&str, Vec<(usize, usize, usize)>> = HashMap::new();
-    let mut category_map: HashMap<&str, &str> = HashMap::new();
-
-    // The rest of the almanac contains a list of maps which describe how to convert numbers from a source category into numbers in a destination category.
-    let re = Regex::new(r"^(\w+)-to-(\w+) map:$").unwrap();
-    let mut l = "left";
-    let mut mappings: Vec<(usize, usize, usize)> = Vec::new();
-    for line in almanac_iterator {
-        if let Some(caps) = re.captures(line) {
-            l = caps.get(1).unwrap().as_str();
-            let r = caps.get(2).unwrap().as_str();
-            category_map.insert(l, r);
-
-            mappings = Vec::new();
+use crate::input::Input;
+
+#[derive(Debug, PartialEq)]
+pub enum AlmanacError {
+    // The almanac does not start with a line listing the seeds
+    InvalidSeeds(String),
+    // Seeds read as ranges need a start and a length for each range, and at least one range
+    UnpairedSeeds(usize),
+    // A map does not start with a line naming its source and destination categories
+    InvalidHeader(String),
+    // A line of a map is not three numbers
+    InvalidMapping(String),
+    // No map converts out of the given category on the way to the destination
+    MissingCategory {
+        category: String,
+        destination: String,
+    },
+    // Following the maps from the source returns to a category already visited
+    CyclicChain(Vec<String>),
+    // More than one map converts out of the same category
+    BranchingChain {
+        category: String,
+        destinations: Vec<String>,
+    },
+    // Two mappings within one map claim the same source numbers
+    OverlappingRanges {
+        source: String,
+        destination: String,
+        first: (usize, usize, usize),
+        second: (usize, usize, usize),
+    },
+}
 
-            continue;
+impl fmt::Display for AlmanacError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlmanacError::InvalidSeeds(line) => write!(f, "Invalid seeds line: {}", line),
+            AlmanacError::UnpairedSeeds(count) => {
+                write!(f, "{} seeds cannot be read as ranges", count)
+            }
+            AlmanacError::InvalidHeader(line) => write!(f, "Invalid map header: {}", line),
+            AlmanacError::InvalidMapping(line) => write!(f, "Invalid mapping: {}", line),
+            AlmanacError::MissingCategory {
+                category,
+                destination,
+            } => write!(f, "No map from {} on the way to {}", category, destination),
+            AlmanacError::CyclicChain(categories) => {
+                write!(f, "Cyclic category chain: {}", categories.join(" -> "))
+            }
+            AlmanacError::BranchingChain {
+                category,
+                destinations,
+            } => write!(
+                f,
+                "Category {} maps to more than one category: {}",
+                category,
+                destinations.join(", ")
+            ),
+            AlmanacError::OverlappingRanges {
+                source,
+                destination,
+                first,
+                second,
+            } => write!(
+                f,
+                "Overlapping source ranges in {}-to-{} map: {:?} and {:?}",
+                source, destination, first, second
+            ),
         }
+    }
+}
 
-        if line == "" {
-            // Sort mappings by source_range_start
-            mappings.sort_by(|a, b| a.1.cmp(&b.1));
-            number_map.insert(l, mappings.clone());
+#[derive(Debug, PartialEq)]
+pub struct CategoryMap<'a> {
+    pub source: &'a str,
+    pub destination: &'a str,
+    // (destination_range_start, source_range_start, range_length), ordered by source_range_start
+    pub mappings: Vec<(usize, usize, usize)>,
+}
 
-            continue;
+impl<'a> CategoryMap<'a> {
+    fn new(source: &'a str, destination: &'a str) -> CategoryMap<'a> {
+        CategoryMap {
+            source,
+            destination,
+            mappings: Vec::new(),
+        }
+    }
+
+    // Sort mappings by source_range_start and check that no two of them overlap
+    fn finalize(&mut self) -> Result<(), AlmanacError> {
+        self.mappings.sort_by_key(|mapping| mapping.1);
+
+        for pair in self.mappings.windows(2) {
+            let (first, second) = (pair[0], pair[1]);
+            if second.1 < first.1 + first.2 {
+                return Err(AlmanacError::OverlappingRanges {
+                    source: self.source.to_string(),
+                    destination: self.destination.to_string(),
+                    first,
+                    second,
+                });
+            }
         }
 
-        let mut numbers = line
-            .split_whitespace()
-            .map(|x| x.trim().parse::<usize>().unwrap());
-        let destination_range_start = numbers.next().unwrap();
-        let source_range_start = numbers.next().unwrap();
-        let range_length = numbers.next().unwrap();
-        mappings.push((destination_range_start, source_range_start, range_length));
+        Ok(())
     }
-    mappings.sort_by(|a, b| a.1.cmp(&b.1));
-    number_map.insert(l, mappings.clone());
 
-    (seeds, number_map, category_map)
-}
+    pub fn convert(&self, number: usize) -> usize {
+        for (destination_range_start, source_range_start, range_length) in &self.mappings {
+            if number < *source_range_start {
+                // Number is outside of the mappings and is mapped directly to the same number
+                break;
+            }
+            if number < source_range_start + range_length {
+                return destination_range_start + (number - source_range_start);
+            }
+        }
+        // If the loop completes, it also means that the number is outside any of the mappings and is unchanged
+        number
+    }
 
-#[wasm_bindgen]
-pub fn day_5_get_lowest_location(almanac: &str) -> usize {
-    let (seeds, number_map, category_map) = parse_almanac(almanac);
+    // Ranges are half open, [start, end)
+    pub fn convert_ranges(&self, ranges: &[(usize, usize)]) -> Vec<(usize, usize)> {
+        let mappings = &self.mappings;
+        if mappings.is_empty() {
+            return ranges.to_vec();
+        }
 
-    let mut ans = usize::MAX;
-    for seed in seeds {
-        let mut category = "seed";
-        let mut number = seed;
-        while category != "location" {
-            for (destination_range_start, source_range_start, range_length) in
-                number_map.get(category).unwrap()
+        // Destination ranges
+        let mut new_ranges = Vec::new();
+        for range in ranges {
+            let mut start = range.0;
+            let end = range.1;
+            for (i, (destination_range_start, source_range_start, range_length)) in
+                mappings.iter().enumerate()
             {
-                if number < *source_range_start {
-                    // Number is outside of the mappings and is mapped directly to the same number
-                    break;
+                // Out of the mapping, check the next mapping
+                if start >= source_range_start + range_length {
+                    if i == mappings.len() - 1 {
+                        // Catch all if the range doesn't sit in any of the mappings
+                        new_ranges.push((start, end));
+                    }
+                    continue;
+                }
+
+                if start < *source_range_start {
+                    // Range starts outside the mapping
+                    if end <= *source_range_start {
+                        // Range is completely outside any mappings
+                        new_ranges.push((start, end));
+                        break;
+                    }
+
+                    // Add the bit outside the mapping
+                    new_ranges.push((start, *source_range_start));
+
+                    // Add the bit inside the mapping
+                    if end <= source_range_start + range_length {
+                        new_ranges.push((
+                            *destination_range_start,
+                            *destination_range_start + (end - source_range_start),
+                        ));
+                        break;
+                    }
+
+                    // Add the bit inside the mapping and continue with the rest that doesn't fit.
+                    new_ranges.push((
+                        *destination_range_start,
+                        *destination_range_start + range_length,
+                    ));
+
+                    start = source_range_start + range_length;
+                    if i == mappings.len() - 1 {
+                        // The rest of the range lies past the last mapping
+                        new_ranges.push((start, end));
+                    }
+                    continue;
                 }
-                if number < source_range_start + range_length {
-                    number = destination_range_start + (number - source_range_start);
+
+                // Start is inside the range
+                if end <= source_range_start + range_length {
+                    new_ranges.push((
+                        *destination_range_start + (start - source_range_start),
+                        *destination_range_start + (end - source_range_start),
+                    ));
                     break;
                 }
+
+                // Add the bit inside the mapping and continue with the rest that doesn't fit.
+                new_ranges.push((
+                    *destination_range_start + (start - source_range_start),
+                    *destination_range_start + range_length,
+                ));
+
+                start = source_range_start + range_length;
+                if i == mappings.len() - 1 {
+                    // The rest of the range lies past the last mapping
+                    new_ranges.push((start, end));
+                }
+            }
+        }
+        new_ranges
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Almanac<'a> {
+    pub seeds: Vec<usize>,
+    pub maps: Vec<CategoryMap<'a>>,
+}
+
+impl<'a> Almanac<'a> {
+    pub fn parse(almanac: &'a Input) -> Result<Almanac<'a>, AlmanacError> {
+        let mut sections = almanac.sections();
+
+        // The first section is a listing of which seeds need to be planted
+        let seeds_line = sections.next().unwrap_or("");
+        let re = Regex::new(r"^seeds:(.*)$").unwrap();
+        let seeds = re
+            .captures(seeds_line)
+            .and_then(|caps| {
+                caps.get(1)
+                    .unwrap()
+                    .as_str()
+                    .split_whitespace()
+                    .map(|x| x.parse::<usize>().ok())
+                    .collect::<Option<Vec<_>>>()
+            })
+            .ok_or_else(|| AlmanacError::InvalidSeeds(seeds_line.to_string()))?;
+
+        // Each of the other sections is a map which describes how to convert numbers from a source category into numbers in a destination category.
+        let re = Regex::new(r"^(\w+)-to-(\w+) map:$").unwrap();
+        let mut maps: Vec<CategoryMap> = Vec::new();
+        for section in sections {
+            let mut lines = section.split('\n');
+            // Sections are never empty, so there is always a header line
+            let header = lines.next().unwrap();
+            let caps = re
+                .captures(header)
+                .ok_or_else(|| AlmanacError::InvalidHeader(header.to_string()))?;
+            let mut map =
+                CategoryMap::new(caps.get(1).unwrap().as_str(), caps.get(2).unwrap().as_str());
+
+            for line in lines {
+                let numbers = line
+                    .split_whitespace()
+                    .map(|x| x.parse::<usize>().ok())
+                    .collect::<Option<Vec<_>>>();
+                match numbers.as_deref() {
+                    Some(&[destination_range_start, source_range_start, range_length]) => map
+                        .mappings
+                        .push((destination_range_start, source_range_start, range_length)),
+                    _ => return Err(AlmanacError::InvalidMapping(line.to_string())),
+                }
+            }
+            maps.push(map);
+        }
+
+        for map in maps.iter_mut() {
+            map.finalize()?;
+        }
+
+        Ok(Almanac { seeds, maps })
+    }
+
+    // The seeds read as pairs of a start and a length, as half open ranges [start, end)
+    pub fn seed_ranges(&self) -> Result<Vec<(usize, usize)>, AlmanacError> {
+        if self.seeds.is_empty() || self.seeds.len() % 2 == 1 {
+            return Err(AlmanacError::UnpairedSeeds(self.seeds.len()));
+        }
+
+        Ok(self
+            .seeds
+            .chunks(2)
+            .map(|pair| (pair[0], pair[0].saturating_add(pair[1])))
+            .collect())
+    }
+
+    // Find the sequence of maps that converts numbers from the source category into the destination category
+    pub fn chain(
+        &self,
+        source: &str,
+        destination: &str,
+    ) -> Result<Vec<&CategoryMap<'a>>, AlmanacError> {
+        let mut chain = Vec::new();
+        let mut visited = vec![source];
+        let mut category = source;
+        while category != destination {
+            let outgoing = self
+                .maps
+                .iter()
+                .filter(|map| map.source == category)
+                .collect::<Vec<_>>();
+
+            let map = match outgoing.len() {
+                0 => {
+                    return Err(AlmanacError::MissingCategory {
+                        category: category.to_string(),
+                        destination: destination.to_string(),
+                    })
+                }
+                1 => outgoing[0],
+                _ => {
+                    return Err(AlmanacError::BranchingChain {
+                        category: category.to_string(),
+                        destinations: outgoing
+                            .iter()
+                            .map(|map| map.destination.to_string())
+                            .collect(),
+                    })
+                }
+            };
+
+            category = map.destination;
+            if visited.contains(&category) {
+                visited.push(category);
+                return Err(AlmanacError::CyclicChain(
+                    visited.iter().map(|c| c.to_string()).collect(),
+                ));
             }
-            // If the loop completes, it also means that the number is outside any of the mappings and is unchanged
-            category = category_map.get(category).unwrap();
+            visited.push(category);
+            chain.push(map);
         }
 
+        Ok(chain)
+    }
+
+    pub fn convert(
+        &self,
+        number: usize,
+        source: &str,
+        destination: &str,
+    ) -> Result<usize, AlmanacError> {
+        Ok(self
+            .chain(source, destination)?
+            .iter()
+            .fold(number, |number, map| map.convert(number)))
+    }
+
+    // Ranges are half open, [start, end)
+    pub fn convert_ranges(
+        &self,
+        ranges: &[(usize, usize)],
+        source: &str,
+        destination: &str,
+    ) -> Result<Vec<(usize, usize)>, AlmanacError> {
+        Ok(self
+            .chain(source, destination)?
+            .iter()
+            .fold(ranges.to_vec(), |ranges, map| map.convert_ranges(&ranges)))
+    }
+}
+
+#[wasm_bindgen]
+pub fn day_5_get_lowest_location(almanac: &str) -> usize {
+    let almanac = Input::new(almanac);
+    let almanac = Almanac::parse(&almanac).unwrap();
+
+    let mut ans = usize::MAX;
+    for seed in &almanac.seeds {
+        let number = almanac.convert(*seed, "seed", "location").unwrap();
+
         if number < ans {
             ans = number;
         }
@@ -104,17 +377,12 @@ pub fn day_5_get_lowest_location(almanac: &str) -> usize {
 
 #[wasm_bindgen]
 pub fn day_5_get_lowest_location_part_2(almanac: &str) -> usize {
-    let (seeds, number_map, category_map) = parse_almanac(almanac);
-
-    let mut seed_ranges = Vec::new();
-    let mut i = 0;
-    while i < seeds.len() - 1 {
-        seed_ranges.push((seeds[i], seeds[i] + seeds[i + 1]));
-        i = i + 2;
-    }
+    let almanac = Input::new(almanac);
+    let almanac = Almanac::parse(&almanac).unwrap();
+    let mut seed_ranges = almanac.seed_ranges().unwrap();
 
     // Remove overlaps
-    seed_ranges.sort_by(|a, b| a.0.cmp(&b.0));
+    seed_ranges.sort_by_key(|range| range.0);
     let mut condensed_seed_ranges = Vec::new();
     let mut prev_range = seed_ranges[0];
     for range in &seed_ranges[1..] {
@@ -139,86 +407,12 @@ pub fn day_5_get_lowest_location_part_2(almanac: &str) -> usize {
     condensed_seed_ranges.push(prev_range);
 
     let mut ans = usize::MAX;
-    for seed_range in condensed_seed_ranges {
-        // Source ranges. Will be updated for each category
-        let mut ranges = vec![seed_range];
-        let mut category = "seed";
-        while category != "location" {
-            // Destination ranges
-            let mut new_ranges = Vec::new();
-            let mappings = number_map.get(category).unwrap();
-            for range in &ranges {
-                let mut start = range.0;
-                let end = range.1;
-                for (i, (destination_range_start, source_range_start, range_length)) in
-                    mappings.iter().enumerate()
-                {
-                    // Out of the mapping, check the next mapping
-                    if start >= source_range_start + range_length {
-                        if i == mappings.len() - 1 {
-                            // Catch all if the range doesn't sit in any of the mappings
-                            new_ranges.push((start, end));
-                        }
-                        continue;
-                    }
-
-                    if start < *source_range_start {
-                        // Range starts outside the mapping
-                        if end <= *source_range_start {
-                            // Range is completely outside any mappings
-                            new_ranges.push((start, end));
-                            break;
-                        }
-
-                        // Add the bit outside the mapping
-                        new_ranges.push((start, *source_range_start));
-
-                        // Add the bit inside the mapping
-                        if end <= source_range_start + range_length {
-                            new_ranges.push((
-                                *destination_range_start,
-                                *destination_range_start + (end - source_range_start),
-                            ));
-                            break;
-                        }
-
-                        // Add the bit inside the mapping and continue with the rest that doesn't fit.
-                        new_ranges.push((
-                            *destination_range_start,
-                            *destination_range_start + range_length,
-                        ));
-
-                        start = source_range_start + range_length;
-                        continue;
-                    }
-
-                    // Start is inside the range
-                    if end <= source_range_start + range_length {
-                        new_ranges.push((
-                            *destination_range_start + (start - source_range_start),
-                            *destination_range_start + (end - source_range_start),
-                        ));
-                        break;
-                    }
-
-                    // Add the bit inside the mapping and continue with the rest that doesn't fit.
-                    new_ranges.push((
-                        *destination_range_start + (start - source_range_start),
-                        *destination_range_start + range_length,
-                    ));
-
-                    start = source_range_start + range_length;
-                    continue;
-                }
-            }
-            ranges = new_ranges;
-            category = category_map.get(category).unwrap();
-        }
-
-        for (start, _) in ranges {
-            if start < ans {
-                ans = start
-            }
+    for (start, _) in almanac
+        .convert_ranges(&condensed_seed_ranges, "seed", "location")
+        .unwrap()
+    {
+        if start < ans {
+            ans = start
         }
     }
 
@@ -265,36 +459,173 @@ mod tests {
 
     #[test]
     fn test_parse_almanac() {
-        let (seeds, number_map, category_map) = parse_almanac(EXAMPLE_ALMANAC);
+        let almanac = Input::new(EXAMPLE_ALMANAC);
+        let almanac = Almanac::parse(&almanac).unwrap();
 
-        assert_eq!(vec![79, 14, 55, 13], seeds);
+        assert_eq!(vec![79, 14, 55, 13], almanac.seeds);
         // Tuples are ordered by the 2nd element
         assert_eq!(
-            HashMap::from([
-                ("seed", vec![(52, 50, 48), (50, 98, 2),]),
-                ("water", vec![(88, 18, 7), (18, 25, 70)]),
-                ("soil", vec![(39, 0, 15), (0, 15, 37,), (37, 52, 2,)]),
+            vec![
+                ("seed", "soil", vec![(52, 50, 48), (50, 98, 2)]),
                 (
+                    "soil",
                     "fertilizer",
+                    vec![(39, 0, 15), (0, 15, 37), (37, 52, 2)]
+                ),
+                (
+                    "fertilizer",
+                    "water",
                     vec![(42, 0, 7), (57, 7, 4), (0, 11, 42), (49, 53, 8)]
                 ),
-                ("light", vec![(81, 45, 19), (68, 64, 13), (45, 77, 23)]),
-                ("temperature", vec![(1, 0, 69), (0, 69, 1)]),
-                ("humidity", vec![(60, 56, 37), (56, 93, 4)])
-            ]),
-            number_map
+                ("water", "light", vec![(88, 18, 7), (18, 25, 70)]),
+                (
+                    "light",
+                    "temperature",
+                    vec![(81, 45, 19), (68, 64, 13), (45, 77, 23)]
+                ),
+                ("temperature", "humidity", vec![(1, 0, 69), (0, 69, 1)]),
+                ("humidity", "location", vec![(60, 56, 37), (56, 93, 4)]),
+            ],
+            almanac
+                .maps
+                .iter()
+                .map(|map| (map.source, map.destination, map.mappings.clone()))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_almanac_convert() {
+        let almanac = Input::new(EXAMPLE_ALMANAC);
+        let almanac = Almanac::parse(&almanac).unwrap();
+
+        assert_eq!(Ok(81), almanac.convert(79, "seed", "soil"));
+        assert_eq!(Ok(81), almanac.convert(79, "seed", "fertilizer"));
+        assert_eq!(Ok(74), almanac.convert(81, "water", "light"));
+        assert_eq!(Ok(82), almanac.convert(79, "seed", "location"));
+        assert_eq!(Ok(43), almanac.convert(43, "location", "location"));
+        assert_eq!(
+            Ok(vec![(46, 47), (63, 64)]),
+            almanac.convert_ranges(&[(82, 83), (46, 47)], "seed", "location")
+        );
+    }
+
+    #[test]
+    fn test_almanac_chain_errors() {
+        let almanac = Input::new(EXAMPLE_ALMANAC);
+        let almanac = Almanac::parse(&almanac).unwrap();
+        assert_eq!(
+            Err(AlmanacError::MissingCategory {
+                category: "location".to_string(),
+                destination: "seed".to_string()
+            }),
+            almanac.convert(0, "soil", "seed")
+        );
+
+        let almanac = Input::new(
+            r#"seeds: 1
+
+            a-to-b map:
+            0 0 1
+
+            b-to-a map:
+            0 0 1"#,
         );
+        let almanac = Almanac::parse(&almanac).unwrap();
+        assert_eq!(
+            Err(AlmanacError::CyclicChain(vec![
+                "a".to_string(),
+                "b".to_string(),
+                "a".to_string()
+            ])),
+            almanac.convert(0, "a", "c")
+        );
+
+        let almanac = Input::new(
+            r#"seeds: 1
+
+            a-to-b map:
+            0 0 1
+
+            a-to-c map:
+            0 0 1"#,
+        );
+        let almanac = Almanac::parse(&almanac).unwrap();
+        assert_eq!(
+            Err(AlmanacError::BranchingChain {
+                category: "a".to_string(),
+                destinations: vec!["b".to_string(), "c".to_string()]
+            }),
+            almanac.convert(0, "a", "c")
+        );
+    }
+
+    #[test]
+    fn test_almanac_parse_errors() {
+        for (error, almanac) in [
+            (AlmanacError::InvalidSeeds("".to_string()), ""),
+            (
+                AlmanacError::InvalidSeeds("seeds: 1 x".to_string()),
+                "seeds: 1 x",
+            ),
+            (
+                AlmanacError::InvalidHeader("a to b map:".to_string()),
+                "seeds: 1\n\na to b map:\n0 0 1",
+            ),
+            (
+                AlmanacError::InvalidMapping("0 0".to_string()),
+                "seeds: 1\n\na-to-b map:\n0 0",
+            ),
+            (
+                AlmanacError::InvalidMapping("0 0 1 2".to_string()),
+                "seeds: 1\n\na-to-b map:\n0 0 1 2",
+            ),
+            (
+                AlmanacError::InvalidMapping("0 -1 1".to_string()),
+                "seeds: 1\n\na-to-b map:\n0 -1 1",
+            ),
+        ] {
+            assert_eq!(Err(error), Almanac::parse(&Input::new(almanac)));
+        }
+    }
+
+    #[test]
+    fn test_almanac_seed_ranges() {
+        let almanac = Input::new(EXAMPLE_ALMANAC);
+        assert_eq!(
+            Ok(vec![(79, 93), (55, 68)]),
+            Almanac::parse(&almanac).unwrap().seed_ranges()
+        );
+
+        for (count, almanac) in [
+            (1, "seeds: 5\n\na-to-b map:\n0 0 1"),
+            (3, "seeds: 5 1 7\n\na-to-b map:\n0 0 1"),
+            (0, "seeds:\n\na-to-b map:\n0 0 1"),
+        ] {
+            let almanac = Input::new(almanac);
+            assert_eq!(
+                Err(AlmanacError::UnpairedSeeds(count)),
+                Almanac::parse(&almanac).unwrap().seed_ranges()
+            );
+        }
+    }
+
+    #[test]
+    fn test_almanac_overlapping_ranges() {
         assert_eq!(
-            HashMap::from([
-                ("temperature", "humidity"),
-                ("seed", "soil"),
-                ("fertilizer", "water"),
-                ("humidity", "location"),
-                ("light", "temperature"),
-                ("water", "light"),
-                ("soil", "fertilizer"),
-            ]),
-            category_map
+            Err(AlmanacError::OverlappingRanges {
+                source: "a".to_string(),
+                destination: "b".to_string(),
+                first: (10, 0, 5),
+                second: (20, 4, 2)
+            }),
+            Almanac::parse(&Input::new(
+                r#"seeds: 1
+
+                a-to-b map:
+                20 4 2
+                10 0 5"#,
+            ))
         );
     }
 
@@ -1,11 +1,15 @@
 use wasm_bindgen::prelude::*;
 
+use std::convert::TryInto;
 use std::iter::zip;
 
-fn parse_document(document: &str) -> (Vec<usize>, Vec<usize>) {
+use crate::input::Input;
+
+fn parse_document(document: &str) -> (Vec<u128>, Vec<u128>) {
     // Document to only contain two lines
     // First is a list of times and the second is a list of distances
-    let mut document_iterator = document.split("\n").map(|line| line.trim());
+    let document = Input::new(document);
+    let mut document_iterator = document.lines();
     let times = document_iterator
         .next()
         .unwrap()
@@ -14,7 +18,7 @@ fn parse_document(document: &str) -> (Vec<usize>, Vec<usize>) {
         .unwrap()
         .trim()
         .split_whitespace()
-        .map(|x| x.trim().parse::<usize>().unwrap())
+        .map(|x| x.trim().parse::<u128>().unwrap())
         .collect();
     let distances = document_iterator
         .next()
@@ -24,38 +28,127 @@ fn parse_document(document: &str) -> (Vec<usize>, Vec<usize>) {
         .unwrap()
         .trim()
         .split_whitespace()
-        .map(|x| x.trim().parse::<usize>().unwrap())
+        .map(|x| x.trim().parse::<u128>().unwrap())
         .collect();
     (times, distances)
 }
 
-fn get_number_of_ways_to_win(time: usize, distance: usize) -> usize {
-    let mut acc = 0;
-    for i in 1..time {
-        if (i * (time - i)) > distance {
-            acc = acc + 1;
+// Largest r with r * r <= n, by Newton's method
+fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+
+    // Start above the root so the iterates decrease monotonically towards it
+    let mut x = 1 << ((128 - n.leading_zeros()) / 2 + 1);
+    loop {
+        let y = (x + n / x) / 2;
+        if y >= x {
+            return x;
         }
+        x = y;
     }
-    acc
 }
 
-#[wasm_bindgen]
-pub fn day_6_product_of_ways_to_beat_each_race(document: &str) -> usize {
-    let mut acc = 1;
+#[derive(Debug, PartialEq)]
+pub struct RaceWins {
+    pub count: u128,
+    // [min_hold, max_hold], inclusive, if the race can be won at all
+    pub hold_range: Option<[u128; 2]>,
+}
+
+// Holding the button for h milliseconds travels h * (time - h), so the winning holds lie strictly between the
+// roots of h^2 - time * h + distance = 0, i.e. (time +/- sqrt(time^2 - 4 * distance)) / 2.
+// For race times of 2^64 or more, where time^2 does not fit in a u128, the first winning hold is found by binary
+// search instead.
+pub fn solve_race(time: u128, distance: u128) -> RaceWins {
+    // A distance too large for a u128 beats any record
+    let beats_record = |hold: u128| {
+        hold.checked_mul(time - hold)
+            .is_none_or(|travelled| travelled > distance)
+    };
+
+    // The furthest distance is travelled holding for half the race
+    let best_hold = time / 2;
+    if !beats_record(best_hold) {
+        return RaceWins {
+            count: 0,
+            hold_range: None,
+        };
+    }
+
+    let min_hold = match time.checked_mul(time) {
+        // The record is beaten at the best hold, so 4 * distance < time^2
+        Some(time_squared) => {
+            let root = isqrt(time_squared - 4 * distance);
+
+            // The integer square root may be off by one from the true root, so nudge onto the first winning hold
+            let mut min_hold = (time - root) / 2;
+            while !beats_record(min_hold) {
+                min_hold += 1;
+            }
+            while min_hold > 0 && beats_record(min_hold - 1) {
+                min_hold -= 1;
+            }
+            min_hold
+        }
+        None => {
+            // Holds up to the best one travel further the longer the button is held
+            let (mut low, mut high) = (0, best_hold);
+            while low < high {
+                let mid = low + (high - low) / 2;
+                if beats_record(mid) {
+                    high = mid;
+                } else {
+                    low = mid + 1;
+                }
+            }
+            low
+        }
+    };
+    // Holds are symmetric around the middle of the race
+    let max_hold = time - min_hold;
+
+    RaceWins {
+        count: max_hold - min_hold + 1,
+        hold_range: Some([min_hold, max_hold]),
+    }
+}
+
+fn get_number_of_ways_to_win(time: u128, distance: u128) -> u128 {
+    solve_race(time, distance).count
+}
+
+// None if the product does not fit in a u128
+pub fn day_6_product_of_race_wins(document: &str) -> Option<u128> {
+    let mut acc: u128 = 1;
 
     let (times, distances) = parse_document(document);
 
     for (time, distance) in zip(times, distances) {
-        acc = acc * get_number_of_ways_to_win(time, distance);
+        acc = acc.checked_mul(get_number_of_ways_to_win(time, distance))?;
     }
 
-    acc
+    Some(acc)
+}
+
+// JavaScript gets a BigInt, or an exception if the answer needs more than 64 bits
+fn to_js(answer: Option<u128>) -> Result<u64, JsValue> {
+    answer
+        .and_then(|answer| answer.try_into().ok())
+        .ok_or_else(|| JsValue::from_str("Answer does not fit in 64 bits"))
 }
 
-fn parse_document_part_2(document: &str) -> (usize, usize) {
+#[wasm_bindgen]
+pub fn day_6_product_of_ways_to_beat_each_race(document: &str) -> Result<u64, JsValue> {
+    to_js(day_6_product_of_race_wins(document))
+}
+
+fn parse_document_part_2(document: &str) -> (u128, u128) {
     // Document to only contain two lines
     // First is a time with a lot of spaces and the second is a distance with a lot of spaces
-    let mut document_iterator = document.split("\n").map(|line| line.trim());
+    let document = Input::new(document);
+    let mut document_iterator = document.lines();
     let time = document_iterator
         .next()
         .unwrap()
@@ -67,7 +160,7 @@ fn parse_document_part_2(document: &str) -> (usize, usize) {
         .map(|x| x.trim())
         .collect::<Vec<&str>>()
         .join("")
-        .parse::<usize>()
+        .parse::<u128>()
         .unwrap();
     let distance = document_iterator
         .next()
@@ -80,16 +173,30 @@ fn parse_document_part_2(document: &str) -> (usize, usize) {
         .map(|x| x.trim())
         .collect::<Vec<&str>>()
         .join("")
-        .parse::<usize>()
+        .parse::<u128>()
         .unwrap();
     (time, distance)
 }
 
 #[wasm_bindgen]
-pub fn day_6_get_number_of_ways_to_win(document: &str) -> usize {
+pub fn day_6_get_number_of_ways_to_win(document: &str) -> Result<u64, JsValue> {
+    let (time, distance) = parse_document_part_2(document);
+
+    to_js(Some(get_number_of_ways_to_win(time, distance)))
+}
+
+pub fn day_6_solve_races(document: &str) -> Vec<RaceWins> {
+    let (times, distances) = parse_document(document);
+
+    zip(times, distances)
+        .map(|(time, distance)| solve_race(time, distance))
+        .collect()
+}
+
+pub fn day_6_solve_race_part_2(document: &str) -> RaceWins {
     let (time, distance) = parse_document_part_2(document);
 
-    get_number_of_ways_to_win(time, distance)
+    solve_race(time, distance)
 }
 
 #[cfg(test)]
@@ -129,7 +236,15 @@ mod tests {
     fn test_day_6_product_of_ways_to_beat_each_race() {
         assert_eq!(
             288,
-            day_6_product_of_ways_to_beat_each_race(EXAMPLE_DOCUMENT)
+            day_6_product_of_ways_to_beat_each_race(EXAMPLE_DOCUMENT).unwrap()
+        );
+        assert_eq!(Some(288), day_6_product_of_race_wins(EXAMPLE_DOCUMENT));
+        // Three races with about 2^64 ways each
+        assert_eq!(
+            None,
+            day_6_product_of_race_wins(
+                "Time: 18446744073709551616 18446744073709551616 18446744073709551616\nDistance: 1 1 1"
+            )
         );
     }
 
@@ -142,6 +257,101 @@ mod tests {
 
     #[test]
     fn test_day_6_get_number_of_ways_to_win() {
-        assert_eq!(71503, day_6_get_number_of_ways_to_win(EXAMPLE_DOCUMENT));
+        assert_eq!(
+            71503,
+            day_6_get_number_of_ways_to_win(EXAMPLE_DOCUMENT).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_isqrt() {
+        for n in 0..10000u128 {
+            let r = isqrt(n);
+            assert!(r * r <= n && (r + 1) * (r + 1) > n);
+        }
+        assert_eq!(u64::MAX as u128, isqrt(u128::MAX));
+        assert_eq!(1 << 63, isqrt(1 << 126));
+        assert_eq!((1 << 63) - 1, isqrt((1 << 126) - 1));
+    }
+
+    #[test]
+    fn test_solve_race() {
+        assert_eq!(
+            RaceWins {
+                count: 4,
+                hold_range: Some([2, 5])
+            },
+            solve_race(7, 9)
+        );
+        // Tying the record is not enough
+        assert_eq!(
+            RaceWins {
+                count: 9,
+                hold_range: Some([11, 19])
+            },
+            solve_race(30, 200)
+        );
+        assert_eq!(
+            RaceWins {
+                count: 0,
+                hold_range: None
+            },
+            solve_race(10, 25)
+        );
+        assert_eq!(
+            RaceWins {
+                count: 1,
+                hold_range: Some([5, 5])
+            },
+            solve_race(10, 24)
+        );
+
+        // Large races against the loop
+        for (time, distance) in [(1000001, 12345678), (999999, 249999500000)] {
+            let expected = (0..=time).filter(|h| h * (time - h) > distance).count() as u128;
+            assert_eq!(expected, solve_race(time, distance).count);
+        }
+        let time = u64::MAX as u128;
+        let wins = solve_race(time, time * 1000);
+        let [min_hold, max_hold] = wins.hold_range.unwrap();
+        assert!(min_hold * (time - min_hold) > time * 1000);
+        assert!((min_hold - 1) * (time - min_hold + 1) <= time * 1000);
+        assert_eq!(time - min_hold, max_hold);
+
+        // Times whose square does not fit in a u128
+        for (time, distance) in [(1 << 64, 1), (u128::MAX, u128::MAX), (1 << 100, 1 << 120)] {
+            let wins = solve_race(time, distance);
+            let [min_hold, max_hold] = wins.hold_range.unwrap();
+            assert!(min_hold
+                .checked_mul(time - min_hold)
+                .is_none_or(|travelled| travelled > distance));
+            assert!((min_hold - 1) * (time - min_hold + 1) <= distance);
+            assert_eq!(time - min_hold, max_hold);
+        }
+        assert_eq!(
+            RaceWins {
+                count: (1 << 64) - 1,
+                hold_range: Some([1, (1 << 64) - 1])
+            },
+            solve_race(1 << 64, 0)
+        );
+    }
+
+    #[test]
+    fn test_day_6_solve_races() {
+        assert_eq!(
+            vec![Some([2, 5]), Some([4, 11]), Some([11, 19])],
+            day_6_solve_races(EXAMPLE_DOCUMENT)
+                .iter()
+                .map(|wins| wins.hold_range)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            RaceWins {
+                count: 71503,
+                hold_range: Some([14, 71516])
+            },
+            day_6_solve_race_part_2(EXAMPLE_DOCUMENT)
+        );
     }
 }
@@ -1,9 +1,16 @@
-pub use part_1::day_7_calculate_total_winnings;
-pub use part_2::day_7_calculate_total_winnings_part_2;
+use wasm_bindgen::prelude::*;
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::input::Input;
+use crate::streaming::LineSolver;
 
 // When derived on enums, variants are ordered by their discriminants. By default, the discriminant is smallest for variants at the top, and largest for variants at the bottom.
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
-enum HandType {
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HandType {
     HighCard,
     OnePair,
     TwoPair,
@@ -13,375 +20,1007 @@ enum HandType {
     FiveOfAKind,
 }
 
-pub mod part_1 {
-    use wasm_bindgen::prelude::*;
+impl HandType {
+    // The named five card hand for a shape, the card counts largest first
+    fn from_shape(shape: &[usize]) -> Option<HandType> {
+        match shape {
+            [5] => Some(HandType::FiveOfAKind),
+            [4, 1] => Some(HandType::FourOfAKind),
+            [3, 2] => Some(HandType::FullHouse),
+            [3, 1, 1] => Some(HandType::ThreeOfAKind),
+            [2, 2, 1] => Some(HandType::TwoPair),
+            [2, 1, 1, 1] => Some(HandType::OnePair),
+            [1, 1, 1, 1, 1] => Some(HandType::HighCard),
+            _ => None,
+        }
+    }
+}
 
-    use std::cmp::Ordering;
-    use std::collections::HashMap;
-    use std::iter::zip;
+impl fmt::Display for HandType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            HandType::HighCard => "High card",
+            HandType::OnePair => "One pair",
+            HandType::TwoPair => "Two pair",
+            HandType::ThreeOfAKind => "Three of a kind",
+            HandType::FullHouse => "Full house",
+            HandType::FourOfAKind => "Four of a kind",
+            HandType::FiveOfAKind => "Five of a kind",
+        };
+        write!(f, "{}", name)
+    }
+}
 
-    use crate::day_7::HandType;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    // Compare the cards in the order they were dealt, as in the puzzle
+    InOrder,
+    // Compare the cards from the largest groups first, and strongest first within groups of the same size, as in poker
+    Poker,
+}
 
-    const CARD_ORDER: [char; 13] = [
-        '2', '3', '4', '5', '6', '7', '8', '9', 'T', 'J', 'Q', 'K', 'A',
-    ];
+// How hands are typed and ranked.
+// Hands are ranked first by their shape, the card counts largest first, using the hierarchy, and then by the tie break.
+#[derive(Debug, Clone)]
+pub struct Ruleset {
+    // Every card, weakest first
+    pub card_order: Vec<char>,
+    // Cards which count as whichever card makes the strongest hand, but are ranked by `card_order` in tie breaks
+    pub wildcards: Vec<char>,
+    pub hand_size: usize,
+    pub tie_break: TieBreak,
+    // Shapes from weakest to strongest. Shapes which are not listed rank below every listed one, and shapes which
+    // rank the same are compared lexicographically.
+    pub hierarchy: Vec<Vec<usize>>,
+}
 
-    #[derive(Debug, PartialEq, Eq)]
-    struct Hand<'a> {
-        cards: &'a str,
-        hand_hype: HandType,
+impl Ruleset {
+    // Part 1
+    pub fn standard() -> Ruleset {
+        Ruleset {
+            card_order: "23456789TJQKA".chars().collect(),
+            wildcards: Vec::new(),
+            hand_size: 5,
+            tie_break: TieBreak::InOrder,
+            hierarchy: Ruleset::standard_hierarchy(),
+        }
     }
 
-    impl Ord for Hand<'_> {
-        fn cmp(&self, other: &Self) -> Ordering {
-            if self.hand_hype == other.hand_hype {
-                for (l, r) in zip(self.cards.chars(), other.cards.chars()) {
-                    if l == r {
-                        continue;
-                    }
-                    return CARD_ORDER
-                        .iter()
-                        .position(|c| c == &l)
-                        .cmp(&CARD_ORDER.iter().position(|c| c == &r));
-                }
-            }
-            self.hand_hype.cmp(&other.hand_hype)
+    // Part 2, where J is a Joker and the weakest card
+    pub fn jokers() -> Ruleset {
+        Ruleset {
+            card_order: "J23456789TQKA".chars().collect(),
+            wildcards: vec!['J'],
+            hand_size: 5,
+            tie_break: TieBreak::InOrder,
+            hierarchy: Ruleset::standard_hierarchy(),
         }
     }
 
-    impl PartialOrd for Hand<'_> {
-        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-            Some(self.cmp(other))
-        }
+    // High card < One pair < Two pair < Three of a kind < Full house < Four of a kind < Five of a kind
+    pub fn standard_hierarchy() -> Vec<Vec<usize>> {
+        vec![
+            vec![1, 1, 1, 1, 1],
+            vec![2, 1, 1, 1],
+            vec![2, 2, 1],
+            vec![3, 1, 1],
+            vec![3, 2],
+            vec![4, 1],
+            vec![5],
+        ]
     }
 
-    fn determine_type(hand: &str) -> HandType {
-        let mut card_counts: HashMap<char, usize> = HashMap::new();
+    fn strength(&self, card: char) -> Option<usize> {
+        self.card_order.iter().position(|c| *c == card)
+    }
 
-        for char in hand.chars() {
-            if let Some(count) = card_counts.get(&char) {
-                card_counts.insert(char, count + 1);
+    // Position of the shape in the hierarchy
+    fn rank(&self, shape: &[usize]) -> Option<usize> {
+        self.hierarchy.iter().position(|listed| listed == shape)
+    }
+
+    // Card counts largest first, with the wildcards counted as whichever cards make the strongest shape
+    fn shape(&self, cards: &str) -> Vec<usize> {
+        let mut card_counts: HashMap<char, usize> = HashMap::new();
+        let mut wildcards = 0;
+        for card in cards.chars() {
+            if self.wildcards.contains(&card) {
+                wildcards += 1;
             } else {
-                card_counts.insert(char, 1);
+                *card_counts.entry(card).or_insert(0) += 1;
             }
         }
 
-        match card_counts.values().len() {
-            1 => return HandType::FiveOfAKind,
-            2 => {
-                for count in card_counts.values() {
-                    if count == &4 || count == &1 {
-                        return HandType::FourOfAKind;
-                    } else if count == &3 || count == &2 {
-                        return HandType::FullHouse;
-                    }
-                }
-                unreachable!();
+        // Cards the wildcards could become which are not in the hand yet
+        let new_cards = self
+            .card_order
+            .iter()
+            .filter(|card| !self.wildcards.contains(card) && !card_counts.contains_key(card))
+            .count();
+        let mut shape = card_counts.into_values().collect::<Vec<_>>();
+        shape.sort_unstable_by(|a, b| b.cmp(a));
+        if wildcards == 0 {
+            return shape;
+        }
+
+        // Lexicographically, adding the wildcards to the largest group always gives the strongest shape
+        if self.hierarchy.is_empty() {
+            match shape.first_mut() {
+                Some(largest) => *largest += wildcards,
+                None => shape.push(wildcards),
             }
-            3 => {
-                for count in card_counts.values() {
-                    if count == &3 {
-                        return HandType::ThreeOfAKind;
-                    } else if count == &2 {
-                        return HandType::TwoPair;
+            return shape;
+        }
+
+        let mut shapes = Vec::new();
+        let groups = shape.len();
+        wildcard_shapes(
+            &mut shape,
+            groups,
+            0,
+            wildcards,
+            wildcards,
+            new_cards,
+            &mut shapes,
+        );
+        shapes
+            .into_iter()
+            .max_by(|a, b| (self.rank(a), a).cmp(&(self.rank(b), b)))
+            .unwrap_or_default()
+    }
+
+    pub fn hand<'a>(&self, cards: &'a str) -> Result<Hand<'a>, HandError> {
+        if cards.chars().count() != self.hand_size {
+            return Err(HandError::WrongSize(cards.to_string()));
+        }
+
+        let mut tie_break = cards
+            .chars()
+            .map(|card| self.strength(card).ok_or(HandError::UnknownCard(card)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if self.tie_break == TieBreak::Poker {
+            let count = |strength: &usize| tie_break.iter().filter(|s| *s == strength).count();
+            let mut grouped = tie_break.clone();
+            grouped.sort_unstable_by(|a, b| (count(b), b).cmp(&(count(a), a)));
+            tie_break = grouped;
+        }
+
+        let shape = self.shape(cards);
+        Ok(Hand {
+            rank: self.rank(&shape),
+            shape,
+            tie_break,
+            cards,
+        })
+    }
+
+    // Which of two hands wins, Greater if it is the left one
+    pub fn compare(&self, left: &str, right: &str) -> Result<Ordering, HandError> {
+        Ok(self.hand(left)?.cmp(&self.hand(right)?))
+    }
+
+    // The card each wildcard in the hand should become to make the shape it is ranked by.
+    // Groups are filled largest and then strongest first, followed by new cards strongest first, so with the standard
+    // hierarchy every wildcard becomes the card there is most of, the strongest of those if there are several, or the
+    // strongest card there is if the hand is all wildcards.
+    pub fn explain_wildcards(&self, cards: &str) -> Result<WildcardSubstitution, HandError> {
+        let hand = self.hand(cards)?;
+
+        let mut card_counts: HashMap<char, usize> = HashMap::new();
+        for card in cards.chars().filter(|card| !self.wildcards.contains(card)) {
+            *card_counts.entry(card).or_insert(0) += 1;
+        }
+        let positions = cards
+            .chars()
+            .enumerate()
+            .filter(|(_, card)| self.wildcards.contains(card))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+
+        // Cards not in the hand, which the wildcards could also become, strongest first
+        let new_cards = self
+            .card_order
+            .iter()
+            .rev()
+            .filter(|card| !self.wildcards.contains(card) && !card_counts.contains_key(card))
+            .map(|card| (*card, 0))
+            .collect::<Vec<_>>();
+        let mut groups = card_counts.into_iter().collect::<Vec<_>>();
+        groups.sort_unstable_by_key(|(card, count)| {
+            std::cmp::Reverse((*count, self.strength(*card)))
+        });
+        groups.extend(new_cards);
+
+        let mut replacements = Vec::new();
+        if !positions.is_empty() {
+            substitute_wildcards(
+                &mut groups,
+                0,
+                positions.len(),
+                &hand.shape,
+                &mut replacements,
+            );
+        }
+
+        let mut replacement = replacements.iter();
+        let substituted = if replacements.is_empty() {
+            cards.to_string()
+        } else {
+            cards
+                .chars()
+                .map(|card| {
+                    if self.wildcards.contains(&card) {
+                        *replacement.next().unwrap()
+                    } else {
+                        card
                     }
-                }
-                unreachable!();
+                })
+                .collect()
+        };
+
+        Ok(WildcardSubstitution {
+            cards: cards.to_string(),
+            positions,
+            replacements,
+            substituted,
+            hand_type: hand.hand_type(),
+        })
+    }
+}
+
+// Adds the wildcards to the groups from `i` on, as many as possible to the earliest groups, until the groups make the
+// target shape. The card each wildcard becomes is left in replacements, which stays empty if there is no way.
+fn substitute_wildcards(
+    groups: &mut [(char, usize)],
+    i: usize,
+    wildcards: usize,
+    target: &[usize],
+    replacements: &mut Vec<char>,
+) -> bool {
+    if wildcards == 0 {
+        let mut shape = groups
+            .iter()
+            .map(|(_, count)| *count)
+            .filter(|count| *count > 0)
+            .collect::<Vec<_>>();
+        shape.sort_unstable_by(|a, b| b.cmp(a));
+        return shape == target;
+    }
+    if i == groups.len() {
+        return false;
+    }
+
+    for extra in (0..=wildcards).rev() {
+        groups[i].1 += extra;
+        let found = substitute_wildcards(groups, i + 1, wildcards - extra, target, replacements);
+        groups[i].1 -= extra;
+        if found {
+            replacements.splice(0..0, std::iter::repeat_n(groups[i].0, extra));
+            return true;
+        }
+    }
+    false
+}
+
+// Every shape made by adding the wildcards to the groups from `i` on, up to `existing`, and then as new groups of at
+// most `largest` cards, using at most `new_cards` more cards
+fn wildcard_shapes(
+    shape: &mut Vec<usize>,
+    existing: usize,
+    i: usize,
+    wildcards: usize,
+    largest: usize,
+    new_cards: usize,
+    shapes: &mut Vec<Vec<usize>>,
+) {
+    if i < existing {
+        for extra in 0..=wildcards {
+            shape[i] += extra;
+            wildcard_shapes(
+                shape,
+                existing,
+                i + 1,
+                wildcards - extra,
+                largest,
+                new_cards,
+                shapes,
+            );
+            shape[i] -= extra;
+        }
+    } else if wildcards == 0 {
+        let mut sorted = shape.clone();
+        sorted.sort_unstable_by(|a, b| b.cmp(a));
+        shapes.push(sorted);
+    } else if new_cards > 0 {
+        // New groups are added largest first so each set of them is only made once
+        for group in (1..=wildcards.min(largest)).rev() {
+            shape.push(group);
+            wildcard_shapes(
+                shape,
+                existing,
+                i,
+                wildcards - group,
+                group,
+                new_cards - 1,
+                shapes,
+            );
+            shape.pop();
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct WildcardSubstitution {
+    pub cards: String,
+    // Indices of the wildcards in the hand
+    pub positions: Vec<usize>,
+    // The card each wildcard becomes, in the same order. Empty if there are no wildcards to replace, or only wildcards
+    // in the ruleset.
+    pub replacements: Vec<char>,
+    // The hand with every wildcard replaced
+    pub substituted: String,
+    pub hand_type: Option<HandType>,
+}
+
+impl fmt::Display for WildcardSubstitution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.replacements.split_first() {
+            Some((first, rest)) => {
+                let replacements = if rest.iter().all(|card| card == first) {
+                    first.to_string()
+                } else {
+                    self.replacements
+                        .iter()
+                        .map(|card| card.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+                write!(
+                    f,
+                    "{}: wildcards at {:?} become {}, making {}",
+                    self.cards, self.positions, replacements, self.substituted
+                )?
             }
-            4 => return HandType::OnePair,
-            5 => return HandType::HighCard,
-            _ => unreachable!(),
+            None => write!(f, "{}: no wildcards to replace", self.cards)?,
         }
+        if let Some(hand_type) = self.hand_type {
+            write!(f, ", {}", hand_type)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum HandError {
+    UnknownCard(char),
+    // A hand with a different number of cards than the ruleset deals
+    WrongSize(String),
+    // A line which is not a hand followed by a bid
+    InvalidLine(String),
+    // A ruleset with more possible hands than can be ranked while streaming
+    TooManyHands,
+}
+
+impl fmt::Display for HandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HandError::UnknownCard(card) => write!(f, "Unknown card {}", card),
+            HandError::WrongSize(cards) => write!(f, "Wrong number of cards in {}", cards),
+            HandError::InvalidLine(line) => write!(f, "Invalid hand and bid {}", line),
+            HandError::TooManyHands => write!(f, "Too many possible hands to rank"),
+        }
+    }
+}
+
+// Field order matters, hands are ordered by their shape's rank in the hierarchy, their shape and then the tie break
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Hand<'a> {
+    rank: Option<usize>,
+    shape: Vec<usize>,
+    // Strength of each card, in the order they are compared
+    tie_break: Vec<usize>,
+    cards: &'a str,
+}
+
+impl Hand<'_> {
+    pub fn cards(&self) -> &str {
+        self.cards
+    }
+
+    // Card counts largest first, with the wildcards counted as the best card for them
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    // None for hands which are not five cards
+    pub fn hand_type(&self) -> Option<HandType> {
+        HandType::from_shape(&self.shape)
+    }
+}
+
+pub fn determine_type(cards: &str, ruleset: &Ruleset) -> Option<HandType> {
+    HandType::from_shape(&ruleset.shape(cards))
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RankedHand {
+    pub cards: String,
+    pub bid: usize,
+    pub hand_type: Option<HandType>,
+    // 1 for the weakest hand
+    pub rank: usize,
+    // rank * bid
+    pub winnings: usize,
+}
+
+fn parse_hand_and_bid<'a>(
+    line: &'a str,
+    ruleset: &Ruleset,
+) -> Result<(Hand<'a>, usize), HandError> {
+    let mut line_iterator = line.split_whitespace();
+    match (
+        line_iterator.next(),
+        line_iterator.next(),
+        line_iterator.next(),
+    ) {
+        (Some(cards), Some(bid), None) => Ok((
+            ruleset.hand(cards)?,
+            bid.parse::<usize>()
+                .map_err(|_| HandError::InvalidLine(line.trim().to_string()))?,
+        )),
+        _ => Err(HandError::InvalidLine(line.trim().to_string())),
     }
+}
+
+// Every hand, in the order they were listed, with its rank and what it contributes to the total winnings
+pub fn day_7_rank_hands(hands: &str, ruleset: &Ruleset) -> Result<Vec<RankedHand>, HandError> {
+    let hands = Input::new(hands);
+    let hands_and_bids = hands
+        .lines()
+        .map(|line| parse_hand_and_bid(line, ruleset))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut order = (0..hands_and_bids.len()).collect::<Vec<_>>();
+    order.sort_by(|a, b| hands_and_bids[*a].0.cmp(&hands_and_bids[*b].0));
+    let mut ranks = vec![0; hands_and_bids.len()];
+    for (i, k) in order.into_iter().enumerate() {
+        ranks[k] = i + 1;
+    }
+
+    Ok(hands_and_bids
+        .into_iter()
+        .zip(ranks)
+        .map(|((hand, bid), rank)| RankedHand {
+            cards: hand.cards.to_string(),
+            bid,
+            hand_type: hand.hand_type(),
+            rank,
+            winnings: rank * bid,
+        })
+        .collect())
+}
+
+pub fn day_7_calculate_total_winnings_with_ruleset(
+    hands: &str,
+    ruleset: &Ruleset,
+) -> Result<usize, HandError> {
+    Ok(day_7_rank_hands(hands, ruleset)?
+        .iter()
+        .map(|hand| hand.winnings)
+        .sum())
+}
 
-    #[wasm_bindgen]
-    pub fn day_7_calculate_total_winnings(hands: &str) -> usize {
-        let mut acc = 0;
-        let mut hands_and_bids: Vec<(Hand, usize)> = hands
-            .split("\n")
-            .map(|line| {
-                let mut line_iterator = line.trim().split_whitespace().map(|x| x.trim());
-                let cards = line_iterator.next().unwrap();
-                let bid = line_iterator.next().unwrap().parse::<usize>().unwrap();
-                (
-                    Hand {
-                        cards: cards,
-                        hand_hype: determine_type(cards),
-                    },
-                    bid,
-                )
+// Running total winnings of hands read a line at a time.
+// Adding a hand moves every stronger hand up a rank, so the total grows by the new hand's winnings plus the bids of
+// every stronger hand. Those are found with a Fenwick tree over every possible hand, stored sparsely as most are unused.
+#[derive(Debug)]
+pub struct TotalWinnings {
+    ruleset: Ruleset,
+    // Number of possible hands, with hand k at index k + 1 in the tree
+    size: u128,
+    // Number of hands and sum of their bids
+    tree: HashMap<u128, (usize, usize)>,
+    bids: usize,
+    total: usize,
+}
+
+impl TotalWinnings {
+    pub fn new(ruleset: Ruleset) -> Result<TotalWinnings, HandError> {
+        // Ranks are written in base hierarchy.len() + 1, with 0 for unlisted shapes, shapes in base hand_size + 1 and
+        // tie breaks in base card_order.len()
+        let base = (ruleset.hand_size as u128 + 1)
+            .checked_mul(ruleset.card_order.len() as u128)
+            .ok_or(HandError::TooManyHands)?;
+        let size = (0..ruleset.hand_size)
+            .try_fold(ruleset.hierarchy.len() as u128 + 1, |size, _| {
+                size.checked_mul(base)
             })
-            .collect();
-        hands_and_bids.sort_by(|a, b| a.0.cmp(&b.0));
+            .ok_or(HandError::TooManyHands)?;
+
+        Ok(TotalWinnings {
+            ruleset,
+            size,
+            tree: HashMap::new(),
+            bids: 0,
+            total: 0,
+        })
+    }
+
+    // Position of the hand among every possible hand, in the same order as comparing them
+    fn key(&self, hand: &Hand) -> u128 {
+        let shape_base = self.ruleset.hand_size as u128 + 1;
+        let card_base = self.ruleset.card_order.len() as u128;
 
-        for (i, (_, bid)) in hands_and_bids.iter().enumerate() {
-            acc = acc + ((i + 1) * bid);
+        let mut key = hand.rank.map_or(0, |rank| rank as u128 + 1);
+        for k in 0..self.ruleset.hand_size {
+            key = key * shape_base + *hand.shape.get(k).unwrap_or(&0) as u128;
+        }
+        for strength in &hand.tie_break {
+            key = key * card_base + *strength as u128;
         }
+        key
+    }
 
-        return acc;
+    // Number of hands and sum of their bids at indices up to i
+    fn prefix(&self, mut i: u128) -> (usize, usize) {
+        let (mut hands, mut bids) = (0, 0);
+        while i > 0 {
+            if let Some((h, b)) = self.tree.get(&i) {
+                hands += h;
+                bids += b;
+            }
+            i -= i & i.wrapping_neg();
+        }
+        (hands, bids)
     }
+}
+
+impl LineSolver for TotalWinnings {
+    type Total = usize;
+    type Error = HandError;
 
-    #[cfg(test)]
-    mod tests {
-        use super::*;
+    fn feed(&mut self, line: &str) -> Result<(), HandError> {
+        let (hand, bid) = parse_hand_and_bid(line, &self.ruleset)?;
+        let index = self.key(&hand) + 1;
+
+        // Equal hands keep the order they were listed in, so count as weaker
+        let (weaker, weaker_bids) = self.prefix(index);
+        self.total += bid * (weaker + 1) + (self.bids - weaker_bids);
+        self.bids += bid;
+
+        let mut i = index;
+        while i <= self.size {
+            let (hands, bids) = self.tree.entry(i).or_insert((0, 0));
+            *hands += 1;
+            *bids += bid;
+            i = match i.checked_add(i & i.wrapping_neg()) {
+                Some(i) => i,
+                None => break,
+            };
+        }
+
+        Ok(())
+    }
+
+    fn total(&self) -> usize {
+        self.total
+    }
+}
+
+fn preset(jokers: bool) -> Ruleset {
+    if jokers {
+        Ruleset::jokers()
+    } else {
+        Ruleset::standard()
+    }
+}
+
+fn to_js(error: HandError) -> JsValue {
+    JsValue::from_str(&error.to_string())
+}
+
+#[wasm_bindgen]
+pub fn day_7_determine_type(cards: &str, jokers: bool) -> Result<HandType, JsValue> {
+    let ruleset = preset(jokers);
+    let hand = ruleset.hand(cards).map_err(to_js)?;
+    // Every five card hand has a type
+    hand.hand_type()
+        .ok_or_else(|| to_js(HandError::WrongSize(cards.to_string())))
+}
+
+// 1 if the left hand wins, -1 if the right one does and 0 for identical hands
+#[wasm_bindgen]
+pub fn day_7_compare_hands(left: &str, right: &str, jokers: bool) -> Result<i32, JsValue> {
+    Ok(preset(jokers).compare(left, right).map_err(to_js)? as i32)
+}
+
+#[wasm_bindgen]
+pub fn day_7_explain_jokers(cards: &str) -> Result<String, JsValue> {
+    Ok(Ruleset::jokers()
+        .explain_wildcards(cards)
+        .map_err(to_js)?
+        .to_string())
+}
+
+#[wasm_bindgen]
+pub fn day_7_calculate_total_winnings(hands: &str) -> usize {
+    day_7_calculate_total_winnings_with_ruleset(hands, &Ruleset::standard()).unwrap()
+}
 
-        const EXAMPLE: &str = r#"32T3K 765
+#[wasm_bindgen]
+pub fn day_7_calculate_total_winnings_part_2(hands: &str) -> usize {
+    day_7_calculate_total_winnings_with_ruleset(hands, &Ruleset::jokers()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::streaming::running_totals;
+
+    const EXAMPLE: &str = r#"32T3K 765
     T55J5 684
     KK677 28
     KTJJT 220
     QQQJA 483"#;
 
-        macro_rules! generate_determine_type_test {
+    macro_rules! generate_determine_type_test {
         ($($name:ident: $value:expr,)*) => {
         $(
             #[test]
             #[allow(non_snake_case)]
             fn $name() {
-                let (cards, expected) = $value;
-                assert_eq!(expected, determine_type(cards));
+                let (cards, ruleset, expected) = $value;
+                assert_eq!(Some(expected), determine_type(cards, &ruleset));
             }
         )*
         }
     }
 
-        generate_determine_type_test! {
-            test_determine_type_AAAAA: ("AAAAA", HandType::FiveOfAKind),
-            test_determine_type_AA8AA: ("AA8AA", HandType::FourOfAKind),
-            test_determine_type_23332: ("23332", HandType::FullHouse),
-            test_determine_type_TTT98: ("TTT98", HandType::ThreeOfAKind),
-            test_determine_type_23432: ("23432", HandType::TwoPair),
-            test_determine_type_A23A4: ("A23A4", HandType::OnePair),
-            test_determine_type_23456: ("23456", HandType::HighCard),
-        }
+    generate_determine_type_test! {
+        test_determine_type_AAAAA: ("AAAAA", Ruleset::standard(), HandType::FiveOfAKind),
+        test_determine_type_AA8AA: ("AA8AA", Ruleset::standard(), HandType::FourOfAKind),
+        test_determine_type_23332: ("23332", Ruleset::standard(), HandType::FullHouse),
+        test_determine_type_TTT98: ("TTT98", Ruleset::standard(), HandType::ThreeOfAKind),
+        test_determine_type_23432: ("23432", Ruleset::standard(), HandType::TwoPair),
+        test_determine_type_A23A4: ("A23A4", Ruleset::standard(), HandType::OnePair),
+        test_determine_type_23456: ("23456", Ruleset::standard(), HandType::HighCard),
+        test_determine_type_KTJJT: ("KTJJT", Ruleset::standard(), HandType::TwoPair),
+        // With Jokers
+        test_determine_type_jokers_AAAAA: ("AAAAA", Ruleset::jokers(), HandType::FiveOfAKind),
+        test_determine_type_jokers_AA8AA: ("AA8AA", Ruleset::jokers(), HandType::FourOfAKind),
+        test_determine_type_jokers_23332: ("23332", Ruleset::jokers(), HandType::FullHouse),
+        test_determine_type_jokers_TTT98: ("TTT98", Ruleset::jokers(), HandType::ThreeOfAKind),
+        test_determine_type_jokers_23432: ("23432", Ruleset::jokers(), HandType::TwoPair),
+        test_determine_type_jokers_A23A4: ("A23A4", Ruleset::jokers(), HandType::OnePair),
+        test_determine_type_jokers_23456: ("23456", Ruleset::jokers(), HandType::HighCard),
+        // Example
+        test_determine_type_jokers_T55J5: ("T55J5", Ruleset::jokers(), HandType::FourOfAKind),
+        test_determine_type_jokers_KTJJT: ("KTJJT", Ruleset::jokers(), HandType::FourOfAKind),
+        test_determine_type_jokers_QQQJA: ("QQQJA", Ruleset::jokers(), HandType::FourOfAKind),
+        // Full house all Js
+        test_determine_type_jokers_JJJJJ: ("JJJJJ", Ruleset::jokers(), HandType::FiveOfAKind),
+        // Originally Four of a kind
+        test_determine_type_jokers_AAJAA: ("AAJAA", Ruleset::jokers(), HandType::FiveOfAKind),
+        test_determine_type_jokers_JJJAJ: ("JJJAJ", Ruleset::jokers(), HandType::FiveOfAKind),
+        // Originally Full house
+        test_determine_type_jokers_AAJAJ: ("AAJAJ", Ruleset::jokers(), HandType::FiveOfAKind),
+        test_determine_type_jokers_JAJAJ: ("JAJAJ", Ruleset::jokers(), HandType::FiveOfAKind),
+        // Originally Three of a kind
+        test_determine_type_jokers_JJAJ2: ("JJAJ2", Ruleset::jokers(), HandType::FourOfAKind),
+        test_determine_type_jokers_AJAA3: ("AJAA3", Ruleset::jokers(), HandType::FourOfAKind),
+        // Originally Two Pair
+        test_determine_type_jokers_JJAA3: ("JJAA3", Ruleset::jokers(), HandType::FourOfAKind),
+        test_determine_type_jokers_22AAJ: ("22AAJ", Ruleset::jokers(), HandType::FullHouse),
+        // Originally One Pair or High card
+        test_determine_type_jokers_JJ234: ("JJ234", Ruleset::jokers(), HandType::ThreeOfAKind),
+        test_determine_type_jokers_J2345: ("J2345", Ruleset::jokers(), HandType::OnePair),
+    }
 
-        #[test]
-        fn test_hand_sort() {
-            let mut hands = ["32T3K", "T55J5", "KK677", "KTJJT", "QQQJA"].map(|cards| Hand {
-                cards: cards,
-                hand_hype: determine_type(cards),
-            });
-            hands.sort();
-            assert_eq!(
-                ["32T3K", "KTJJT", "KK677", "T55J5", "QQQJA"],
-                hands.map(|hand| hand.cards)
-            );
-        }
+    fn sort_hands<'a>(hands: [&'a str; 5], ruleset: &Ruleset) -> [&'a str; 5] {
+        let mut hands = hands.map(|cards| ruleset.hand(cards).unwrap());
+        hands.sort();
+        hands.map(|hand| hand.cards)
+    }
 
-        #[test]
-        fn test_day_7_calculate_total_winnings() {
-            assert_eq!(6440, day_7_calculate_total_winnings(EXAMPLE));
-        }
+    #[test]
+    fn test_hand_sort() {
+        let hands = ["32T3K", "T55J5", "KK677", "KTJJT", "QQQJA"];
+        assert_eq!(
+            ["32T3K", "KTJJT", "KK677", "T55J5", "QQQJA"],
+            sort_hands(hands, &Ruleset::standard())
+        );
+        assert_eq!(
+            ["32T3K", "KK677", "T55J5", "QQQJA", "KTJJT"],
+            sort_hands(hands, &Ruleset::jokers())
+        );
+
+        let poker = Ruleset {
+            tie_break: TieBreak::Poker,
+            ..Ruleset::standard()
+        };
+        assert_eq!(
+            ["23456", "2345A", "A2233", "3344K", "KKQQ2"],
+            sort_hands(["23456", "2345A", "3344K", "A2233", "KKQQ2"], &poker)
+        );
     }
-}
 
-pub mod part_2 {
-    use wasm_bindgen::prelude::*;
+    #[test]
+    fn test_ruleset_variants() {
+        // Deuces and Jokers wild, so two Jokers and two deuces make five of a kind
+        let wild = Ruleset {
+            card_order: "J23456789TQKA".chars().collect(),
+            wildcards: vec!['J', '2'],
+            ..Ruleset::standard()
+        };
+        assert_eq!(Some(HandType::FiveOfAKind), determine_type("J2J2A", &wild));
+        assert_eq!(Some(HandType::FourOfAKind), determine_type("J2K3K", &wild));
 
-    use std::cmp::Ordering;
-    use std::collections::HashMap;
-    use std::iter::zip;
+        // Hands with more cards are still ranked by their shape
+        let seven_cards = Ruleset {
+            hand_size: 7,
+            ..Ruleset::jokers()
+        };
+        assert_eq!(vec![4, 2, 1], seven_cards.shape("AAJ33A2"));
+        assert_eq!(None, determine_type("AAJ33A2", &seven_cards));
+        assert!(seven_cards.hand("AAJ33A2").unwrap() > seven_cards.hand("AAA3322").unwrap());
+        assert_eq!(
+            Ok(3 * 1 + 5 * 2),
+            day_7_calculate_total_winnings_with_ruleset("AAJ3322 5\n23456KK 3", &seven_cards)
+        );
 
-    use crate::day_7::HandType;
+        // Two pair beats three of a kind, so a Joker makes a second pair rather than three of a kind
+        let mut hierarchy = Ruleset::standard_hierarchy();
+        hierarchy.swap(2, 3);
+        let two_pair_high = Ruleset {
+            hierarchy,
+            ..Ruleset::jokers()
+        };
+        assert_eq!(
+            Some(HandType::TwoPair),
+            determine_type("J2234", &two_pair_high)
+        );
+        assert_eq!(
+            Some(HandType::ThreeOfAKind),
+            determine_type("J2234", &Ruleset::jokers())
+        );
+        assert_eq!(
+            Ok(Ordering::Greater),
+            two_pair_high.compare("22334", "AAA34")
+        );
+        assert_eq!(
+            Ok(Ordering::Less),
+            Ruleset::jokers().compare("22334", "AAA34")
+        );
+        // Two Jokers can become different cards
+        assert_eq!(
+            Some(HandType::TwoPair),
+            determine_type("JJ234", &two_pair_high)
+        );
+        assert_eq!(
+            Some(HandType::FourOfAKind),
+            determine_type("JJJ23", &two_pair_high)
+        );
 
-    const CARD_ORDER: [char; 13] = [
-        'J', '2', '3', '4', '5', '6', '7', '8', '9', 'T', 'Q', 'K', 'A',
-    ];
+        // Shapes which are not listed rank below every listed one
+        let only_five_of_a_kind = Ruleset {
+            hierarchy: vec![vec![5]],
+            ..Ruleset::standard()
+        };
+        assert_eq!(
+            Ok(Ordering::Greater),
+            only_five_of_a_kind.compare("22222", "AAAAK")
+        );
+        assert_eq!(
+            Ok(Ordering::Greater),
+            only_five_of_a_kind.compare("AAAAK", "AAAKK")
+        );
 
-    #[derive(Debug, PartialEq, Eq)]
-    struct Hand<'a> {
-        cards: &'a str,
-        hand_hype: HandType,
+        // Without a hierarchy shapes are only compared lexicographically
+        let lexicographic = Ruleset {
+            hierarchy: Vec::new(),
+            ..Ruleset::jokers()
+        };
+        assert_eq!(
+            Some(HandType::ThreeOfAKind),
+            determine_type("J2234", &lexicographic)
+        );
     }
 
-    impl Ord for Hand<'_> {
-        fn cmp(&self, other: &Self) -> Ordering {
-            if self.hand_hype == other.hand_hype {
-                for (l, r) in zip(self.cards.chars(), other.cards.chars()) {
-                    if l == r {
-                        continue;
-                    }
-                    return CARD_ORDER
-                        .iter()
-                        .position(|c| c == &l)
-                        .cmp(&CARD_ORDER.iter().position(|c| c == &r));
-                }
-            }
-            self.hand_hype.cmp(&other.hand_hype)
-        }
+    #[test]
+    fn test_invalid_hands() {
+        let ruleset = Ruleset::standard();
+        assert_eq!(Err(HandError::UnknownCard('1')), ruleset.hand("1AAAA"));
+        assert_eq!(
+            Err(HandError::WrongSize("AAAA".to_string())),
+            ruleset.hand("AAAA")
+        );
+        assert_eq!(
+            Err(HandError::InvalidLine("AAAAA x".to_string())),
+            day_7_calculate_total_winnings_with_ruleset("AAAAA x", &ruleset)
+        );
+        assert_eq!(
+            Err(HandError::InvalidLine("AAAAA".to_string())),
+            day_7_calculate_total_winnings_with_ruleset("AAAAA", &ruleset)
+        );
     }
 
-    impl PartialOrd for Hand<'_> {
-        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-            Some(self.cmp(other))
-        }
+    #[test]
+    fn test_compare_hands() {
+        assert_eq!(1, day_7_compare_hands("KK677", "KTJJT", false).unwrap());
+        assert_eq!(-1, day_7_compare_hands("KK677", "KTJJT", true).unwrap());
+        assert_eq!(0, day_7_compare_hands("KTJJT", "KTJJT", true).unwrap());
+        // Jokers are the weakest card in tie breaks, even when they make the same type
+        assert_eq!(1, day_7_compare_hands("QQQQ2", "JKKK2", true).unwrap());
+        assert_eq!(
+            Err(HandError::UnknownCard('X')),
+            Ruleset::standard().compare("KK677", "XXXXX")
+        );
     }
 
-    fn determine_type(hand: &str) -> HandType {
-        let mut card_counts: HashMap<char, usize> = HashMap::new();
+    #[test]
+    fn test_day_7_determine_type() {
+        assert_eq!(
+            HandType::TwoPair,
+            day_7_determine_type("KTJJT", false).unwrap()
+        );
+        assert_eq!(
+            HandType::FourOfAKind,
+            day_7_determine_type("KTJJT", true).unwrap()
+        );
+        assert_eq!(
+            Some(HandType::FullHouse),
+            Ruleset::jokers().hand("22AAJ").unwrap().hand_type()
+        );
+    }
 
-        for char in hand.chars() {
-            if let Some(count) = card_counts.get(&char) {
-                card_counts.insert(char, count + 1);
-            } else {
-                card_counts.insert(char, 1);
-            }
-        }
+    #[test]
+    fn test_explain_wildcards() {
+        let ruleset = Ruleset::jokers();
+        assert_eq!(
+            WildcardSubstitution {
+                cards: "KTJJT".to_string(),
+                positions: vec![2, 3],
+                replacements: vec!['T', 'T'],
+                substituted: "KTTTT".to_string(),
+                hand_type: Some(HandType::FourOfAKind),
+            },
+            ruleset.explain_wildcards("KTJJT").unwrap()
+        );
+        // Two pair, either pair works but the stronger is picked
+        assert_eq!(
+            "22AAJ: wildcards at [4] become A, making 22AAA, Full house",
+            day_7_explain_jokers("22AAJ").unwrap()
+        );
+        assert_eq!(
+            "JJJJJ: wildcards at [0, 1, 2, 3, 4] become A, making AAAAA, Five of a kind",
+            day_7_explain_jokers("JJJJJ").unwrap()
+        );
+        assert_eq!(
+            "32T3K: no wildcards to replace, One pair",
+            day_7_explain_jokers("32T3K").unwrap()
+        );
 
-        let distinct_values = card_counts.values().len();
-
-        // Do the normal tests if no J is involved
-        if !card_counts.contains_key(&'J') {
-            match distinct_values {
-                1 => return HandType::FiveOfAKind,
-                2 => {
-                    for count in card_counts.values() {
-                        if count == &4 || count == &1 {
-                            return HandType::FourOfAKind;
-                        } else if count == &3 || count == &2 {
-                            return HandType::FullHouse;
-                        }
-                    }
-                    unreachable!();
-                }
-                3 => {
-                    for count in card_counts.values() {
-                        if count == &3 {
-                            return HandType::ThreeOfAKind;
-                        } else if count == &2 {
-                            return HandType::TwoPair;
-                        }
-                    }
-                    unreachable!();
-                }
-                4 => return HandType::OnePair,
-                5 => return HandType::HighCard,
-                _ => unreachable!(),
-            }
+        // The substitution always makes the hand type the ruleset determined
+        for cards in ["J2345", "JJ234", "AJAA3", "T55J5", "QQQJA", "2J2J3"] {
+            let explanation = ruleset.explain_wildcards(cards).unwrap();
+            assert_eq!(
+                determine_type(cards, &ruleset),
+                determine_type(&explanation.substituted, &Ruleset::standard())
+            );
         }
 
-        match distinct_values {
-            // Joker will become the other
-            1 | 2 => return HandType::FiveOfAKind,
-            3 => {
-                if !card_counts.values().find(|&&x| x == 3).is_none() {
-                    // If there's a 3 the Joker will make a 4 of a kind either way
-                    return HandType::FourOfAKind;
-                }
-
-                // The remaining case is a Two Pair
-                let count = card_counts.get(&'J').unwrap();
-                if count == &2 {
-                    // If the J is the pair, the best case is a Four of a kind
-                    return HandType::FourOfAKind;
-                }
-
-                // If its not, then the best is a Full house
-                return HandType::FullHouse;
-            }
-            // If the J not the pair, it will combine with the pair because its better than a Two pair
-            // If it is the pair, it can only combine with one of the rest to make a Three of a kind
-            4 => return HandType::ThreeOfAKind,
-            // The worst you can get is a One Pair because the J will pair off with anything
-            5 => return HandType::OnePair,
-            _ => unreachable!(),
+        // When two pair beats three of a kind, two Jokers become different cards
+        let mut hierarchy = Ruleset::standard_hierarchy();
+        hierarchy.swap(2, 3);
+        let two_pair_high = Ruleset {
+            hierarchy,
+            ..Ruleset::jokers()
+        };
+        let explanation = two_pair_high.explain_wildcards("JJ234").unwrap();
+        assert_eq!(vec!['4', '3'], explanation.replacements);
+        assert_eq!("43234", explanation.substituted);
+        assert_eq!(Some(HandType::TwoPair), explanation.hand_type);
+        assert_eq!(
+            "JJ234: wildcards at [0, 1] become 4, 3, making 43234, Two pair",
+            explanation.to_string()
+        );
+        for cards in ["J2234", "JJ234", "JJJ23", "J2345", "2J2J3"] {
+            let explanation = two_pair_high.explain_wildcards(cards).unwrap();
+            assert_eq!(
+                determine_type(cards, &two_pair_high),
+                determine_type(&explanation.substituted, &two_pair_high)
+            );
         }
     }
 
-    #[wasm_bindgen]
-    pub fn day_7_calculate_total_winnings_part_2(hands: &str) -> usize {
-        let mut acc = 0;
-        let mut hands_and_bids: Vec<(Hand, usize)> = hands
-            .split("\n")
-            .map(|line| {
-                let mut line_iterator = line.trim().split_whitespace().map(|x| x.trim());
-                let cards = line_iterator.next().unwrap();
-                let bid = line_iterator.next().unwrap().parse::<usize>().unwrap();
-                (
-                    Hand {
-                        cards: cards,
-                        hand_hype: determine_type(cards),
-                    },
-                    bid,
-                )
-            })
-            .collect();
-        hands_and_bids.sort_by(|a, b| a.0.cmp(&b.0));
+    #[test]
+    fn test_day_7_rank_hands() {
+        let ranked = day_7_rank_hands(EXAMPLE, &Ruleset::jokers()).unwrap();
+        assert_eq!(
+            vec![(1, 765), (3, 2052), (2, 56), (5, 1100), (4, 1932)],
+            ranked
+                .iter()
+                .map(|hand| (hand.rank, hand.winnings))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            RankedHand {
+                cards: "KTJJT".to_string(),
+                bid: 220,
+                hand_type: Some(HandType::FourOfAKind),
+                rank: 5,
+                winnings: 1100,
+            },
+            ranked[3]
+        );
+    }
 
-        for (i, (_, bid)) in hands_and_bids.iter().enumerate() {
-            acc = acc + ((i + 1) * bid);
-        }
+    #[test]
+    fn test_total_winnings() {
+        for ruleset in [
+            Ruleset::standard(),
+            Ruleset::jokers(),
+            Ruleset {
+                tie_break: TieBreak::Poker,
+                ..Ruleset::jokers()
+            },
+            Ruleset {
+                hierarchy: vec![vec![2, 2, 1], vec![5], vec![3, 1, 1]],
+                ..Ruleset::jokers()
+            },
+        ] {
+            let lines = EXAMPLE.split('\n').collect::<Vec<_>>();
+            let totals = running_totals(
+                EXAMPLE.as_bytes(),
+                TotalWinnings::new(ruleset.clone()).unwrap(),
+            )
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
 
-        return acc;
-    }
-
-    #[cfg(test)]
-    mod tests {
-        use super::*;
-
-        const EXAMPLE: &str = r#"32T3K 765
-        T55J5 684
-        KK677 28
-        KTJJT 220
-        QQQJA 483"#;
-
-        macro_rules! generate_determine_type_test {
-            ($($name:ident: $value:expr,)*) => {
-            $(
-                #[test]
-                #[allow(non_snake_case)]
-                fn $name() {
-                    let (cards, expected) = $value;
-                    assert_eq!(expected, determine_type(cards));
-                }
-            )*
+            // Each running total is the total winnings of the hands so far
+            for (n, total) in totals.into_iter().enumerate() {
+                assert_eq!(
+                    day_7_calculate_total_winnings_with_ruleset(&lines[..=n].join("\n"), &ruleset),
+                    Ok(total)
+                );
             }
         }
 
-        generate_determine_type_test! {
-            test_determine_type_AAAAA: ("AAAAA", HandType::FiveOfAKind),
-            test_determine_type_AA8AA: ("AA8AA", HandType::FourOfAKind),
-            test_determine_type_23332: ("23332", HandType::FullHouse),
-            test_determine_type_TTT98: ("TTT98", HandType::ThreeOfAKind),
-            test_determine_type_23432: ("23432", HandType::TwoPair),
-            test_determine_type_A23A4: ("A23A4", HandType::OnePair),
-            test_determine_type_23456: ("23456", HandType::HighCard),
-            // With Js
-            // Example
-            test_determine_type_T55J5: ("T55J5", HandType::FourOfAKind),
-            test_determine_type_KTJJT: ("KTJJT", HandType::FourOfAKind),
-            test_determine_type_QQQJA: ("QQQJA", HandType::FourOfAKind),
-            // Full house all Js
-            test_determine_type_JJJJJ: ("JJJJJ", HandType::FiveOfAKind),
-            // Originally Four of a kind
-            test_determine_type_AAJAA: ("AAJAA", HandType::FiveOfAKind),
-            test_determine_type_JJJAJ: ("JJJAJ", HandType::FiveOfAKind),
-            // Originally Full house
-            test_determine_type_AAJAJ: ("AAJAJ", HandType::FiveOfAKind),
-            test_determine_type_JAJAJ: ("JAJAJ", HandType::FiveOfAKind),
-            // Originally Three of a kind
-            test_determine_type_JJAJ2: ("JJAJ2", HandType::FourOfAKind),
-            test_determine_type_AJAA3: ("AJAA3", HandType::FourOfAKind),
-            // Originally Two Pair
-            test_determine_type_JJAA3: ("JJAA3", HandType::FourOfAKind),
-            test_determine_type_22AAJ: ("22AAJ", HandType::FullHouse),
-        }
+        // Repeated hands rank in the order they were listed
+        let hands = "AAAAA 1\n22222 10\nAAAAA 100\n22222 1000";
+        let totals = running_totals(
+            hands.as_bytes(),
+            TotalWinnings::new(Ruleset::standard()).unwrap(),
+        )
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+        assert_eq!(
+            Ok(*totals.last().unwrap()),
+            day_7_calculate_total_winnings_with_ruleset(hands, &Ruleset::standard())
+        );
 
-        #[test]
-        fn test_hand_sort() {
-            let mut hands = ["32T3K", "T55J5", "KK677", "KTJJT", "QQQJA"].map(|cards| Hand {
-                cards: cards,
-                hand_hype: determine_type(cards),
-            });
-            hands.sort();
-            assert_eq!(
-                ["32T3K", "KK677", "T55J5", "QQQJA", "KTJJT"],
-                hands.map(|hand| hand.cards)
-            );
-        }
+        let huge = Ruleset {
+            hand_size: 40,
+            ..Ruleset::standard()
+        };
+        assert_eq!(
+            HandError::TooManyHands,
+            TotalWinnings::new(huge).unwrap_err()
+        );
+    }
 
-        #[test]
-        fn test_day_7_calculate_total_winnings_part_2() {
-            assert_eq!(5905, day_7_calculate_total_winnings_part_2(EXAMPLE));
-        }
+    #[test]
+    fn test_day_7_calculate_total_winnings() {
+        assert_eq!(6440, day_7_calculate_total_winnings(EXAMPLE));
+        assert_eq!(
+            6440,
+            day_7_calculate_total_winnings(&format!("\n{}\n\n", EXAMPLE))
+        );
+    }
+
+    #[test]
+    fn test_day_7_calculate_total_winnings_part_2() {
+        assert_eq!(5905, day_7_calculate_total_winnings_part_2(EXAMPLE));
     }
 }
@@ -4,8 +4,10 @@ use std::collections::HashMap;
 
 use regex::Regex;
 
-fn parse_documents(documents: &str) -> (&str, HashMap<&str, (&str, &str)>) {
-    let mut document_iterator = documents.split("\n").map(|line| line.trim());
+use crate::input::Input;
+
+fn parse_documents(documents: &Input) -> (&str, HashMap<&str, (&str, &str)>) {
+    let mut document_iterator = documents.lines();
 
     let left_right_instructions = document_iterator.next().unwrap().trim();
     document_iterator.next();
@@ -26,7 +28,8 @@ fn parse_documents(documents: &str) -> (&str, HashMap<&str, (&str, &str)>) {
 
 #[wasm_bindgen]
 pub fn day_8_steps_required_following_instructions(documents: &str) -> usize {
-    let (instructions, nodes) = parse_documents(documents);
+    let documents = Input::new(documents);
+    let (instructions, nodes) = parse_documents(&documents);
 
     let mut steps = 0;
     let mut i = 0;
@@ -101,7 +104,8 @@ fn to_next_end<'a>(
 
 #[wasm_bindgen]
 pub fn day_8_steps_required_following_instructions_part_2(documents: &str) -> usize {
-    let (instructions, nodes) = parse_documents(documents);
+    let documents = Input::new(documents);
+    let (instructions, nodes) = parse_documents(&documents);
 
     let mut memo: HashMap<(&str, usize), ((&str, usize), usize)> = HashMap::new();
 
@@ -196,7 +200,8 @@ mod tests {
 
     #[test]
     fn test_identify_starting_nodes() {
-        let (_, nodes) = parse_documents(EXAMPLE_3);
+        let documents = Input::new(EXAMPLE_3);
+        let (_, nodes) = parse_documents(&documents);
 
         let mut starting_nodes = identify_starting_nodes(&nodes);
         let mut expected = vec!["11A", "22A"];
@@ -1,53 +1,141 @@
 use wasm_bindgen::prelude::*;
 
-fn calculate_sequences(history: Vec<isize>) -> Vec<Vec<isize>> {
-    let mut sequences: Vec<Vec<isize>> = vec![history];
+use std::convert::TryInto;
+use std::fmt;
 
-    loop {
-        let mut cont = false;
+use crate::input::Input;
+use crate::streaming::LineSolver;
 
-        let curr = sequences.last().unwrap();
-        let mut next: Vec<isize> = Vec::new();
-        let n = curr.len();
-
-        for i in 1..n {
-            let diff = curr[i] - curr[i - 1];
+#[derive(Debug, PartialEq)]
+pub enum ExtrapolationError {
+    Overflow,
+    // The differences never reach a row of all zeroes
+    NotPolynomial,
+    // A history which is not whitespace separated integers
+    InvalidHistory(String),
+}
 
-            // If any of the values are non-zero, we need to repeat the process
-            if !cont && diff != 0 {
-                cont = true;
+impl fmt::Display for ExtrapolationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtrapolationError::Overflow => write!(f, "Extrapolation overflowed"),
+            ExtrapolationError::NotPolynomial => {
+                write!(f, "History differences never reach all zeroes")
             }
-
-            next.push(diff);
+            ExtrapolationError::InvalidHistory(history) => write!(f, "Invalid history {}", history),
         }
+    }
+}
+
+fn gcd(a: u128, b: u128) -> u128 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// The history in Newton's forward difference form, value(t) = sum over k of C(t, k) * (k-th difference at 0),
+// where t is the index into the history and may lie before or after it.
+#[derive(Debug, PartialEq)]
+pub struct NewtonPolynomial {
+    coefficients: Vec<i128>,
+    length: usize,
+}
 
-        if !cont {
-            break;
+impl NewtonPolynomial {
+    pub fn from_history(history: &[i128]) -> Result<NewtonPolynomial, ExtrapolationError> {
+        let mut coefficients = Vec::new();
+        let mut sequence = history.to_vec();
+
+        while !sequence.iter().all(|x| *x == 0) {
+            // A single non-zero value has no differences left to settle
+            if sequence.len() == 1 {
+                return Err(ExtrapolationError::NotPolynomial);
+            }
+            coefficients.push(sequence[0]);
+            sequence = sequence
+                .windows(2)
+                .map(|pair| pair[1].checked_sub(pair[0]))
+                .collect::<Option<Vec<_>>>()
+                .ok_or(ExtrapolationError::Overflow)?;
+        }
+        if sequence.is_empty() {
+            return Err(ExtrapolationError::NotPolynomial);
         }
 
-        sequences.push(next);
+        Ok(NewtonPolynomial {
+            coefficients,
+            length: history.len(),
+        })
     }
 
-    sequences
-}
+    pub fn value_at(&self, t: i128) -> Result<i128, ExtrapolationError> {
+        let mut acc: i128 = 0;
+        // C(t, 0)
+        let mut binomial: i128 = 1;
 
-fn determine_next_value(history: Vec<isize>) -> isize {
-    let sequences = calculate_sequences(history);
+        for (k, coefficient) in self.coefficients.iter().enumerate() {
+            let term = binomial
+                .checked_mul(*coefficient)
+                .ok_or(ExtrapolationError::Overflow)?;
+            acc = acc.checked_add(term).ok_or(ExtrapolationError::Overflow)?;
 
-    let mut acc = 0;
+            // No further binomial is needed after the last coefficient
+            if k + 1 == self.coefficients.len() {
+                break;
+            }
+
+            // C(t, k + 1) = C(t, k) * (t - k) / (k + 1), which divides exactly. Dividing out what C(t, k) shares
+            // with k + 1 first leaves a divisor of t - k, so nothing larger than C(t, k + 1) is ever computed.
+            let k = k as i128;
+            let g = gcd(binomial.unsigned_abs(), (k + 1) as u128) as i128;
+            let factor = t.checked_sub(k).ok_or(ExtrapolationError::Overflow)? / ((k + 1) / g);
+            binomial = (binomial / g)
+                .checked_mul(factor)
+                .ok_or(ExtrapolationError::Overflow)?;
+        }
 
-    for i in 0..sequences.len() {
-        acc = acc + sequences[i].last().unwrap();
+        Ok(acc)
     }
 
-    acc
+    // Value `steps` after the last value of the history
+    pub fn extrapolate_forwards(&self, steps: u64) -> Result<i128, ExtrapolationError> {
+        self.value_at(self.length as i128 - 1 + steps as i128)
+    }
+
+    // Value `steps` before the first value of the history
+    pub fn extrapolate_backwards(&self, steps: u64) -> Result<i128, ExtrapolationError> {
+        self.value_at(-(steps as i128))
+    }
+}
+
+fn parse_history(history: &str) -> Result<Vec<i128>, ExtrapolationError> {
+    history
+        .split_whitespace()
+        .map(|x| {
+            x.parse::<i128>()
+                .map_err(|_| ExtrapolationError::InvalidHistory(history.to_string()))
+        })
+        .collect()
+}
+
+fn determine_next_value(history: Vec<isize>) -> isize {
+    let history = history.iter().map(|x| *x as i128).collect::<Vec<_>>();
+
+    NewtonPolynomial::from_history(&history)
+        .and_then(|polynomial| polynomial.extrapolate_forwards(1))
+        .unwrap()
+        .try_into()
+        .unwrap()
 }
 
 #[wasm_bindgen]
 pub fn day_9_sum_of_extrapolated_values(report: &str) -> isize {
     let mut acc: isize = 0;
 
-    for history in report.split("\n").map(|line| line.trim()) {
+    let report = Input::new(report);
+    for history in report.lines() {
         acc = acc
             + determine_next_value(
                 history
@@ -61,24 +149,21 @@ pub fn day_9_sum_of_extrapolated_values(report: &str) -> isize {
 }
 
 fn determine_previous_value(history: Vec<isize>) -> isize {
-    let sequences = calculate_sequences(history);
+    let history = history.iter().map(|x| *x as i128).collect::<Vec<_>>();
 
-    let mut acc = 0;
-
-    let n = sequences.len();
-
-    for i in 1..n + 1 {
-        acc = sequences[n - i][0] - acc;
-    }
-
-    acc
+    NewtonPolynomial::from_history(&history)
+        .and_then(|polynomial| polynomial.extrapolate_backwards(1))
+        .unwrap()
+        .try_into()
+        .unwrap()
 }
 
 #[wasm_bindgen]
 pub fn day_9_sum_of_extrapolated_previous_values(report: &str) -> isize {
     let mut acc: isize = 0;
 
-    for history in report.split("\n").map(|line| line.trim()) {
+    let report = Input::new(report);
+    for history in report.lines() {
         acc = acc
             + determine_previous_value(
                 history
@@ -91,10 +176,89 @@ pub fn day_9_sum_of_extrapolated_previous_values(report: &str) -> isize {
     acc
 }
 
+// Sum of the values `steps` after the end of each history
+pub fn day_9_sum_of_values_after(report: &str, steps: u64) -> Result<i128, ExtrapolationError> {
+    let mut acc: i128 = 0;
+
+    let report = Input::new(report);
+    for history in report.lines() {
+        let value = NewtonPolynomial::from_history(&parse_history(history)?)?
+            .extrapolate_forwards(steps)?;
+        acc = acc.checked_add(value).ok_or(ExtrapolationError::Overflow)?;
+    }
+
+    Ok(acc)
+}
+
+// Sum of the values `steps` before the start of each history
+pub fn day_9_sum_of_values_before(report: &str, steps: u64) -> Result<i128, ExtrapolationError> {
+    let mut acc: i128 = 0;
+
+    let report = Input::new(report);
+    for history in report.lines() {
+        let value = NewtonPolynomial::from_history(&parse_history(history)?)?
+            .extrapolate_backwards(steps)?;
+        acc = acc.checked_add(value).ok_or(ExtrapolationError::Overflow)?;
+    }
+
+    Ok(acc)
+}
+
+// Running sum of the values `steps` after, or before, each history read a line at a time
+#[derive(Debug)]
+pub struct ExtrapolatedSum {
+    steps: u64,
+    backwards: bool,
+    total: i128,
+}
+
+impl ExtrapolatedSum {
+    pub fn after(steps: u64) -> ExtrapolatedSum {
+        ExtrapolatedSum {
+            steps,
+            backwards: false,
+            total: 0,
+        }
+    }
+
+    pub fn before(steps: u64) -> ExtrapolatedSum {
+        ExtrapolatedSum {
+            steps,
+            backwards: true,
+            total: 0,
+        }
+    }
+}
+
+impl LineSolver for ExtrapolatedSum {
+    type Total = i128;
+    type Error = ExtrapolationError;
+
+    fn feed(&mut self, line: &str) -> Result<(), ExtrapolationError> {
+        let polynomial = NewtonPolynomial::from_history(&parse_history(line)?)?;
+        let value = if self.backwards {
+            polynomial.extrapolate_backwards(self.steps)?
+        } else {
+            polynomial.extrapolate_forwards(self.steps)?
+        };
+        self.total = self
+            .total
+            .checked_add(value)
+            .ok_or(ExtrapolationError::Overflow)?;
+        Ok(())
+    }
+
+    fn total(&self) -> i128 {
+        self.total
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::streaming::{final_total, running_totals};
+
     const EXAMPLE: &str = r#"0 3 6 9 12 15
     1 3 6 10 15 21
     10 13 16 21 30 45"#;
@@ -130,4 +294,103 @@ mod tests {
     fn test_day_9_sum_of_extrapolated_previous_values() {
         assert_eq!(2, day_9_sum_of_extrapolated_previous_values(EXAMPLE));
     }
+
+    #[test]
+    fn test_newton_polynomial() {
+        let polynomial = NewtonPolynomial::from_history(&[10, 13, 16, 21, 30, 45]).unwrap();
+
+        assert_eq!(Ok(10), polynomial.value_at(0));
+        assert_eq!(Ok(45), polynomial.value_at(5));
+        assert_eq!(Ok(68), polynomial.extrapolate_forwards(1));
+        assert_eq!(Ok(5), polynomial.extrapolate_backwards(1));
+        // Extending the history by hand: -4 5 10 13 16 21 30 45 68 101 146
+        assert_eq!(Ok(146), polynomial.extrapolate_forwards(3));
+        assert_eq!(Ok(-4), polynomial.extrapolate_backwards(2));
+        assert_eq!(Ok(45), polynomial.extrapolate_forwards(0));
+
+        // Constant and empty differences
+        assert_eq!(
+            Ok(7),
+            NewtonPolynomial::from_history(&[7, 7, 7])
+                .unwrap()
+                .extrapolate_forwards(1000000000)
+        );
+        assert_eq!(
+            Ok(0),
+            NewtonPolynomial::from_history(&[0]).unwrap().value_at(-5)
+        );
+
+        // Far extrapolations whose answers fit even though t * (t - 1) does not
+        assert_eq!(
+            Ok(u64::MAX as i128 + 2),
+            NewtonPolynomial::from_history(&[0, 1, 2])
+                .unwrap()
+                .extrapolate_forwards(u64::MAX)
+        );
+        let t = u64::MAX as i128;
+        assert_eq!(
+            Ok(t * ((t - 1) / 2)),
+            NewtonPolynomial::from_history(&[0, 0, 1, 3, 6])
+                .unwrap()
+                .extrapolate_forwards(u64::MAX - 4)
+        );
+        assert_eq!(
+            Ok(t * ((t + 1) / 2)),
+            NewtonPolynomial::from_history(&[0, 0, 1, 3, 6])
+                .unwrap()
+                .extrapolate_backwards(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_newton_polynomial_errors() {
+        // 1 2 4 8 16 never settles
+        assert_eq!(
+            Err(ExtrapolationError::NotPolynomial),
+            NewtonPolynomial::from_history(&[1, 2, 4, 8, 16])
+        );
+        assert_eq!(
+            Err(ExtrapolationError::NotPolynomial),
+            NewtonPolynomial::from_history(&[])
+        );
+        assert_eq!(
+            Err(ExtrapolationError::Overflow),
+            NewtonPolynomial::from_history(&[i128::MIN, i128::MAX, i128::MIN])
+        );
+        // t^2 is larger than an i128 can hold
+        assert_eq!(
+            Err(ExtrapolationError::Overflow),
+            NewtonPolynomial::from_history(&[0, 1, 4, 9, 16])
+                .unwrap()
+                .extrapolate_forwards(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_day_9_sum_of_values() {
+        assert_eq!(Ok(114), day_9_sum_of_values_after(EXAMPLE, 1));
+        assert_eq!(Ok(2), day_9_sum_of_values_before(EXAMPLE, 1));
+        assert_eq!(
+            Err(ExtrapolationError::NotPolynomial),
+            day_9_sum_of_values_after("1 2 4 8 16", 1)
+        );
+    }
+
+    #[test]
+    fn test_extrapolated_sum() {
+        assert_eq!(
+            vec![18, 46, 114],
+            running_totals(EXAMPLE.as_bytes(), ExtrapolatedSum::after(1))
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        );
+        assert_eq!(
+            Some(2),
+            final_total(EXAMPLE.as_bytes(), ExtrapolatedSum::before(1)).unwrap()
+        );
+        assert_eq!(
+            Err(ExtrapolationError::InvalidHistory("1 x 3".to_string())),
+            day_9_sum_of_values_after("1 x 3", 1)
+        );
+    }
 }
@@ -0,0 +1,85 @@
+// Puzzle input as every day expects it: \n line endings, no whitespace around lines and no blank lines before or after
+// it, whatever editor or platform it was saved from.
+#[derive(Debug, PartialEq)]
+pub struct Input {
+    text: String,
+    tabs: bool,
+    non_ascii: bool,
+}
+
+impl Input {
+    pub fn new(raw: &str) -> Input {
+        let text = raw.replace("\r\n", "\n").replace('\r', "\n");
+        let lines = text.split('\n').map(|line| line.trim()).collect::<Vec<_>>();
+
+        let start = lines
+            .iter()
+            .position(|line| !line.is_empty())
+            .unwrap_or(lines.len());
+        let end = lines
+            .iter()
+            .rposition(|line| !line.is_empty())
+            .map_or(start, |end| end + 1);
+
+        Input {
+            text: lines[start..end].join("\n"),
+            tabs: raw.contains('\t'),
+            non_ascii: !raw.is_ascii(),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    // Every line, with none at all for empty input
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.text.split('\n').filter(move |_| !self.text.is_empty())
+    }
+
+    // Groups of lines separated by one or more blank lines
+    pub fn sections(&self) -> impl Iterator<Item = &str> {
+        self.text
+            .split("\n\n")
+            .map(|section| section.trim_matches('\n'))
+            .filter(|section| !section.is_empty())
+    }
+
+    // Tabs anywhere in the raw input, which are easily mistaken for spaces in grids
+    pub fn has_tabs(&self) -> bool {
+        self.tabs
+    }
+
+    // Characters outside ASCII anywhere in the raw input, which no puzzle uses
+    pub fn has_non_ascii(&self) -> bool {
+        self.non_ascii
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input() {
+        let input = Input::new("\r\n\n  ab \rcd\r\n\r\n\nef\n\n");
+        assert_eq!("ab\ncd\n\n\nef", input.as_str());
+        assert_eq!(
+            vec!["ab", "cd", "", "", "ef"],
+            input.lines().collect::<Vec<_>>()
+        );
+        assert_eq!(vec!["ab\ncd", "ef"], input.sections().collect::<Vec<_>>());
+        assert!(!input.has_tabs() && !input.has_non_ascii());
+
+        let input = Input::new("a\tb\n\té");
+        assert_eq!("a\tb\né", input.as_str());
+        assert!(input.has_tabs() && input.has_non_ascii());
+
+        for empty in ["", "\n", " \r\n \n"] {
+            let input = Input::new(empty);
+            assert_eq!("", input.as_str());
+            assert_eq!(None, input.lines().next());
+            assert_eq!(None, input.sections().next());
+        }
+    }
+}
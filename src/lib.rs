@@ -20,6 +20,8 @@ pub mod day_6;
 pub mod day_7;
 pub mod day_8;
 pub mod day_9;
+pub mod input;
+pub mod streaming;
 mod utils;
 
 use wasm_bindgen::prelude::*;
@@ -0,0 +1,146 @@
+use std::fmt;
+use std::io::{self, BufRead, Split};
+
+// A solver which takes its input a record at a time, so that inputs too large to hold in memory can be read from any
+// BufRead. Records are lines unless the solver says otherwise.
+pub trait LineSolver {
+    type Total;
+    type Error: fmt::Display;
+
+    const SEPARATOR: u8 = b'\n';
+
+    fn feed(&mut self, line: &str) -> Result<(), Self::Error>;
+
+    // The answer for every record fed so far
+    fn total(&self) -> Self::Total;
+}
+
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    // The record number, counting from 1, and what was wrong with it
+    InvalidLine(usize, String),
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::Io(error) => write!(f, "Could not read input: {}", error),
+            StreamError::InvalidLine(n, message) => write!(f, "Line {}: {}", n, message),
+        }
+    }
+}
+
+// The solver's total after each record, skipping blank records. Stops after the first error.
+pub struct RunningTotals<R, S> {
+    records: Split<R>,
+    solver: S,
+    n: usize,
+    failed: bool,
+}
+
+impl<R: BufRead, S: LineSolver> RunningTotals<R, S> {
+    // The solver with every record read so far fed to it
+    pub fn solver(&self) -> &S {
+        &self.solver
+    }
+}
+
+impl<R: BufRead, S: LineSolver> Iterator for RunningTotals<R, S> {
+    type Item = Result<S::Total, StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        loop {
+            let record = match self.records.next()? {
+                Ok(record) => record,
+                Err(error) => {
+                    self.failed = true;
+                    return Some(Err(StreamError::Io(error)));
+                }
+            };
+            self.n += 1;
+
+            let result = match std::str::from_utf8(&record) {
+                Ok(record) if record.trim().is_empty() => continue,
+                Ok(record) => self
+                    .solver
+                    .feed(record.trim())
+                    .map(|_| self.solver.total())
+                    .map_err(|error| StreamError::InvalidLine(self.n, error.to_string())),
+                Err(_) => Err(StreamError::InvalidLine(self.n, "Not UTF-8".to_string())),
+            };
+            self.failed = result.is_err();
+            return Some(result);
+        }
+    }
+}
+
+pub fn running_totals<R: BufRead, S: LineSolver>(reader: R, solver: S) -> RunningTotals<R, S> {
+    RunningTotals {
+        records: reader.split(S::SEPARATOR),
+        solver,
+        n: 0,
+        failed: false,
+    }
+}
+
+// The total after every record, or None if there were none
+pub fn final_total<R: BufRead, S: LineSolver>(
+    reader: R,
+    solver: S,
+) -> Result<Option<S::Total>, StreamError> {
+    let mut total = None;
+    for running_total in running_totals(reader, solver) {
+        total = Some(running_total?);
+    }
+
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Sums the numbers on each line
+    struct Sum(i64);
+
+    impl LineSolver for Sum {
+        type Total = i64;
+        type Error = std::num::ParseIntError;
+
+        fn feed(&mut self, line: &str) -> Result<(), Self::Error> {
+            self.0 += line.parse::<i64>()?;
+            Ok(())
+        }
+
+        fn total(&self) -> i64 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_running_totals() {
+        let input = "1\r\n2\n\n  3 \n\n".as_bytes();
+        assert_eq!(
+            vec![1, 3, 6],
+            running_totals(input, Sum(0))
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        );
+        assert_eq!(Some(6), final_total(input, Sum(0)).unwrap());
+        assert_eq!(None, final_total("\n\n".as_bytes(), Sum(0)).unwrap());
+
+        let mut totals = running_totals("1\nx\n2".as_bytes(), Sum(0));
+        assert_eq!(1, totals.next().unwrap().unwrap());
+        assert!(matches!(
+            totals.next(),
+            Some(Err(StreamError::InvalidLine(2, _)))
+        ));
+        assert!(totals.next().is_none());
+        assert_eq!(1, totals.solver().total());
+    }
+}
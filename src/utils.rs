@@ -1,5 +1,7 @@
 use std::fmt;
 
+use crate::input::Input;
+
 #[derive(Debug)]
 pub struct Move2DError {}
 
@@ -32,7 +34,8 @@ pub fn print_2d_matrix(matrix: &Vec<Vec<char>>) {
 pub fn parse_2d_matrix(s: &str) -> Vec<Vec<char>> {
     let mut ret = Vec::new();
 
-    for line in s.split("\n").map(|line| line.trim()) {
+    let s = Input::new(s);
+    for line in s.lines() {
         let mut row = Vec::new();
         for c in line.chars() {
             row.push(c);
@@ -54,3 +57,111 @@ pub fn move_2d(
     }
     return Ok((new_x as usize, new_y as usize));
 }
+
+// Brent's cycle detection on the sequence start, step(start), step(step(start)), ...
+// Returns (mu, lambda, the state at mu): the index of the first state in the cycle, the length of the cycle and the
+// state it starts with. The search is bounded by n: it calls step O(n) times at most, and gives up with Err(the nth
+// state) if that is reached before the cycle is found. So Ok always has mu <= n, and n = usize::MAX searches until the
+// cycle is found. Err is returned straight away for n = 0.
+pub(crate) fn find_cycle<T, F>(start: &T, mut step: F, n: usize) -> Result<(usize, usize, T), T>
+where
+    T: Clone + PartialEq,
+    F: FnMut(&T) -> T,
+{
+    if n == 0 {
+        return Err(start.clone());
+    }
+
+    // Find the cycle length by moving the hare ahead in increasing powers of two. The hare is always at state
+    // `steps`.
+    let mut power = 1;
+    let mut lambda = 1;
+    let mut steps = 1;
+    let mut tortoise = start.clone();
+    let mut hare = step(start);
+    while tortoise != hare {
+        if steps == n {
+            return Err(hare);
+        }
+        if power == lambda {
+            tortoise = hare.clone();
+            power *= 2;
+            lambda = 0;
+        }
+        hare = step(&hare);
+        lambda += 1;
+        steps += 1;
+    }
+
+    // Keep the hare lambda steps ahead of the tortoise, they first meet at the start of the cycle
+    let mut tortoise = start.clone();
+    let mut hare = start.clone();
+    for _ in 0..lambda {
+        hare = step(&hare);
+    }
+    let mut mu = 0;
+    while tortoise != hare {
+        if mu == n {
+            return Err(tortoise);
+        }
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        mu += 1;
+    }
+
+    Ok((mu, lambda, tortoise))
+}
+
+// State after n steps, skipping whole repetitions of the cycle the sequence falls into
+pub fn nth_state<T, F>(start: &T, mut step: F, n: usize) -> T
+where
+    T: Clone + PartialEq,
+    F: FnMut(&T) -> T,
+{
+    // Only search for the cycle as far as the nth state, so a short run never pays for a long lead-in
+    let (mu, lambda, mut state) = match find_cycle(start, &mut step, n) {
+        Ok(cycle) => cycle,
+        Err(state) => return state,
+    };
+
+    for _ in 0..(n - mu) % lambda {
+        state = step(&state);
+    }
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_cycle() {
+        // 0 -> 1 -> 2 -> 3 -> 4 -> 2
+        let step = |x: &usize| if *x == 4 { 2 } else { x + 1 };
+
+        assert_eq!(Ok((2, 3, 2)), find_cycle(&0, step, usize::MAX));
+        assert_eq!(Ok((0, 3, 3)), find_cycle(&3, step, usize::MAX));
+        assert_eq!(Ok((0, 1, 7)), find_cycle(&7, |x: &usize| *x, usize::MAX));
+        assert_eq!(Err(3), find_cycle(&0, step, 3));
+        assert_eq!(Err(4), find_cycle(&0, step, 4));
+
+        assert_eq!(1, nth_state(&0, step, 1));
+        assert_eq!(4, nth_state(&0, step, 4));
+        assert_eq!(2, nth_state(&0, step, 5));
+        assert_eq!(4, nth_state(&0, step, 1000000000));
+        assert_eq!(0, nth_state(&0, step, 0));
+
+        // Short runs stop at the nth state rather than looking for a cycle far beyond it
+        let mut calls = 0;
+        let counting_step = |x: &usize| {
+            calls += 1;
+            if *x == 1000 {
+                0
+            } else {
+                x + 1
+            }
+        };
+        assert_eq!(3, nth_state(&0, counting_step, 3));
+        assert_eq!(3, calls);
+    }
+}